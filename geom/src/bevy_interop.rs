@@ -0,0 +1,80 @@
+//! Conversions to and from Bevy types, enabled with the `bevy` feature.
+//!
+//! Positions map straight onto Bevy's axes, so `y` increases upwards on screen.
+
+use std::convert::TryFrom;
+
+use bevy::{
+    math::{Vec2, Vec3},
+    transform::components::Transform,
+};
+
+use crate::{NegativeFloatError, NonNegativeFloat, Point, Rect, Size, Space};
+
+impl<S: Space> From<Point<f32, S>> for Vec2 {
+    fn from(point: Point<f32, S>) -> Vec2 {
+        Vec2::new(point.x(), point.y())
+    }
+}
+
+impl<S: Space> From<Point<i32, S>> for Vec2 {
+    fn from(point: Point<i32, S>) -> Vec2 {
+        Vec2::new(point.x() as f32, point.y() as f32)
+    }
+}
+
+impl<S: Space> From<Vec2> for Point<f32, S> {
+    fn from(vec: Vec2) -> Self {
+        Point::new(vec.x, vec.y)
+    }
+}
+
+impl<S: Space> From<Size<NonNegativeFloat, S>> for Vec2 {
+    fn from(size: Size<NonNegativeFloat, S>) -> Vec2 {
+        Vec2::new(size.width().get(), size.height().get())
+    }
+}
+
+impl<S: Space> From<Size<u32, S>> for Vec2 {
+    fn from(size: Size<u32, S>) -> Vec2 {
+        Vec2::new(size.width() as f32, size.height() as f32)
+    }
+}
+
+impl<S: Space> TryFrom<Vec2> for Size<NonNegativeFloat, S> {
+    type Error = NegativeFloatError;
+
+    fn try_from(vec: Vec2) -> Result<Self, NegativeFloatError> {
+        Ok(Size::new(
+            NonNegativeFloat::new(vec.x)?,
+            NonNegativeFloat::new(vec.y)?,
+        ))
+    }
+}
+
+impl<S: Space> Rect<f32, NonNegativeFloat, S> {
+    /// A transform placing a centre-origin sprite, such as one sized with
+    /// [`Rect::sprite_size`], over the rect at depth `z`.
+    pub fn centred_transform(&self, z: f32) -> Transform {
+        let center = Vec2::from(self.center());
+        Transform::from_translation(Vec3::new(center.x, center.y, z))
+    }
+
+    pub fn sprite_size(&self) -> Vec2 {
+        self.size().into()
+    }
+}
+
+impl<S: Space> Rect<i32, u32, S> {
+    /// A transform placing a centre-origin sprite, such as one sized with
+    /// [`Rect::sprite_size`], over the rect at depth `z`.
+    pub fn centred_transform(&self, z: f32) -> Transform {
+        // Halving as floats keeps the centre of odd sizes exact.
+        let center = Vec2::from(self.position()) + Vec2::from(self.size()) * 0.5;
+        Transform::from_translation(Vec3::new(center.x, center.y, z))
+    }
+
+    pub fn sprite_size(&self) -> Vec2 {
+        self.size().into()
+    }
+}
@@ -1,6 +1,16 @@
-use std::fmt::Debug;
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{Debug, Display},
+    iter::Sum,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign},
+};
 
-#[derive(Copy, Clone)]
+/// An `f32` that is never negative or NaN. Zero and positive infinity are allowed.
+///
+/// Arithmetic keeps values non-negative by clamping, so subtracting a larger value gives zero, as
+/// do results that would be NaN like `0 / 0`.
+#[derive(Copy, Clone, PartialEq, Default)]
 pub struct NonNegativeFloat(f32);
 
 impl Debug for NonNegativeFloat {
@@ -9,19 +19,134 @@ impl Debug for NonNegativeFloat {
     }
 }
 
-pub struct NegativeFloatError;
+impl Display for NonNegativeFloat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// The value given to [`NonNegativeFloat::new`] was negative or NaN.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NegativeFloatError {
+    value: f32,
+}
+
+impl NegativeFloatError {
+    /// The rejected value.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+}
+
+impl Display for NegativeFloatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.value.is_nan() {
+            write!(f, "expected a non-negative number, got NaN")
+        } else {
+            write!(f, "expected a non-negative number, got {}", self.value)
+        }
+    }
+}
+
+impl std::error::Error for NegativeFloatError {}
 
 impl NonNegativeFloat {
+    pub const ZERO: NonNegativeFloat = NonNegativeFloat(0.0);
+
     pub fn new(x: f32) -> Result<Self, NegativeFloatError> {
-        if x.is_normal() && x >= 0.0 {
-            Ok(NonNegativeFloat(x))
+        if x >= 0.0 {
+            // Adding zero turns -0.0 into 0.0, so equal values always compare equal.
+            Ok(NonNegativeFloat(x + 0.0))
         } else {
-            Err(NegativeFloatError)
+            Err(NegativeFloatError { value: x })
         }
     }
+
+    /// Clamps `x` to be non-negative, mapping NaN to zero.
+    pub fn clamped(x: f32) -> Self {
+        if x > 0.0 {
+            NonNegativeFloat(x)
+        } else {
+            NonNegativeFloat(0.0)
+        }
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl From<NonNegativeFloat> for f32 {
+    fn from(x: NonNegativeFloat) -> f32 {
+        x.0
+    }
+}
+
+impl From<NonNegativeFloat> for f64 {
+    fn from(x: NonNegativeFloat) -> f64 {
+        x.0 as f64
+    }
+}
+
+impl From<u32> for NonNegativeFloat {
+    fn from(x: u32) -> Self {
+        NonNegativeFloat(x as f32)
+    }
+}
+
+impl TryFrom<f32> for NonNegativeFloat {
+    type Error = NegativeFloatError;
+
+    fn try_from(x: f32) -> Result<Self, NegativeFloatError> {
+        NonNegativeFloat::new(x)
+    }
+}
+
+// NaN is never stored, so the ordering is total.
+impl Eq for NonNegativeFloat {}
+
+impl PartialOrd for NonNegativeFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NonNegativeFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
 }
 
-pub trait GeomUnit {}
+macro_rules! impl_non_negative_float_op {
+    ($op:ident, $fn:ident, $assign_op:ident, $assign_fn:ident, $symbol:tt) => {
+        impl $op for NonNegativeFloat {
+            type Output = NonNegativeFloat;
+
+            fn $fn(self, other: NonNegativeFloat) -> NonNegativeFloat {
+                NonNegativeFloat::clamped(self.0 $symbol other.0)
+            }
+        }
+
+        impl $assign_op for NonNegativeFloat {
+            fn $assign_fn(&mut self, other: NonNegativeFloat) {
+                *self = *self $symbol other;
+            }
+        }
+    };
+}
+
+impl_non_negative_float_op!(Add, add, AddAssign, add_assign, +);
+impl_non_negative_float_op!(Sub, sub, SubAssign, sub_assign, -);
+impl_non_negative_float_op!(Mul, mul, MulAssign, mul_assign, *);
+impl_non_negative_float_op!(Div, div, DivAssign, div_assign, /);
+
+impl Sum for NonNegativeFloat {
+    fn sum<I: Iterator<Item = NonNegativeFloat>>(iter: I) -> Self {
+        iter.fold(NonNegativeFloat::ZERO, Add::add)
+    }
+}
+
+pub trait GeomUnit: Copy {}
 
 impl GeomUnit for f32 {}
 impl GeomUnit for NonNegativeFloat {}
@@ -32,3 +157,71 @@ pub trait NonNegativeGeomUnit: GeomUnit {}
 
 impl NonNegativeGeomUnit for NonNegativeFloat {}
 impl NonNegativeGeomUnit for u32 {}
+
+/// A position unit along with the non-negative unit of sizes measured along it.
+pub trait PositionUnit: GeomUnit + PartialOrd + Add<Output = Self> + Sub<Output = Self> {
+    type Size: NonNegativeGeomUnit;
+
+    /// Moves forwards by `size`.
+    fn offset(self, size: Self::Size) -> Self;
+
+    /// The distance forwards to `other`, or zero if `other` is behind.
+    fn distance_to(self, other: Self) -> Self::Size;
+
+    fn midpoint(self, other: Self) -> Self;
+
+    /// Adds `other`, clamping to the unit's range rather than overflowing.
+    fn saturating_add(self, other: Self) -> Self;
+
+    /// Subtracts `other`, clamping to the unit's range rather than overflowing.
+    fn saturating_sub(self, other: Self) -> Self;
+}
+
+impl PositionUnit for i32 {
+    type Size = u32;
+
+    fn offset(self, size: u32) -> i32 {
+        (self as i64 + size as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    fn distance_to(self, other: i32) -> u32 {
+        (other as i64 - self as i64).clamp(0, u32::MAX as i64) as u32
+    }
+
+    fn midpoint(self, other: i32) -> i32 {
+        (self as i64 + other as i64).div_euclid(2) as i32
+    }
+
+    fn saturating_add(self, other: i32) -> i32 {
+        i32::saturating_add(self, other)
+    }
+
+    fn saturating_sub(self, other: i32) -> i32 {
+        i32::saturating_sub(self, other)
+    }
+}
+
+impl PositionUnit for f32 {
+    type Size = NonNegativeFloat;
+
+    fn offset(self, size: NonNegativeFloat) -> f32 {
+        self + size.get()
+    }
+
+    fn distance_to(self, other: f32) -> NonNegativeFloat {
+        NonNegativeFloat::clamped(other - self)
+    }
+
+    fn midpoint(self, other: f32) -> f32 {
+        (self + other) * 0.5
+    }
+
+    // Floats reach infinity rather than overflowing.
+    fn saturating_add(self, other: f32) -> f32 {
+        self + other
+    }
+
+    fn saturating_sub(self, other: f32) -> f32 {
+        self - other
+    }
+}
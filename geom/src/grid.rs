@@ -0,0 +1,412 @@
+use std::collections::VecDeque;
+
+use crate::{Point, Rect, ScreenTiles, Size, Space};
+
+/// Where existing cells stay when a [`Grid`] is resized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Offset of the old cells within the new grid along one axis, negative when shrinking.
+    fn offset(start: bool, end: bool, old: u32, new: u32) -> i32 {
+        let growth = new as i64 - old as i64;
+        let offset = match (start, end) {
+            (true, _) => 0,
+            (_, true) => growth,
+            _ => growth.div_euclid(2),
+        };
+        offset as i32
+    }
+
+    fn offsets(self, old: (u32, u32), new: (u32, u32)) -> (i32, i32) {
+        use Anchor::*;
+        let left = matches!(self, TopLeft | Left | BottomLeft);
+        let right = matches!(self, TopRight | Right | BottomRight);
+        let top = matches!(self, TopLeft | Top | TopRight);
+        let bottom = matches!(self, BottomLeft | Bottom | BottomRight);
+        (
+            Anchor::offset(left, right, old.0, new.0),
+            Anchor::offset(top, bottom, old.1, new.1),
+        )
+    }
+}
+
+/// The cells on the line from `from` to `to`, both included, with no diagonal gaps.
+pub fn rasterise_line<S: Space>(from: Point<i32, S>, to: Point<i32, S>) -> Vec<Point<i32, S>> {
+    let (mut x, mut y) = (from.x() as i64, from.y() as i64);
+    let (x1, y1) = (to.x() as i64, to.y() as i64);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx + dy;
+
+    let mut cells = Vec::with_capacity((dx - dy + 1) as usize);
+    loop {
+        cells.push(Point::new(x as i32, y as i32));
+        if x == x1 && y == y1 {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// The cells along the inside edge of `rect`.
+pub fn rasterise_rect_outline<S: Space>(rect: &Rect<i32, u32, S>) -> Vec<Point<i32, S>> {
+    if rect.is_empty() {
+        return Vec::new();
+    }
+    let (left, top) = (rect.left(), rect.top());
+    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+
+    let mut cells: Vec<_> = (left..=right).map(|x| Point::new(x, top)).collect();
+    if bottom > top {
+        cells.extend((left..=right).map(|x| Point::new(x, bottom)));
+    }
+    for y in top + 1..bottom {
+        cells.push(Point::new(left, y));
+        if right > left {
+            cells.push(Point::new(right, y));
+        }
+    }
+    cells
+}
+
+/// Every cell in `rect`, row by row.
+pub fn rasterise_rect<S: Space>(rect: &Rect<i32, u32, S>) -> Vec<Point<i32, S>> {
+    (rect.top()..rect.bottom())
+        .flat_map(|y| (rect.left()..rect.right()).map(move |x| Point::new(x, y)))
+        .collect()
+}
+
+/// A rectangular grid of cells with the origin at the top left.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T, S = ScreenTiles> {
+    size: Size<u32, S>,
+    cells: Vec<T>,
+}
+
+impl<T: Clone, S: Space> Grid<T, S> {
+    pub fn new(size: Size<u32, S>, fill: T) -> Self {
+        Grid {
+            size,
+            cells: vec![fill; size.width() as usize * size.height() as usize],
+        }
+    }
+
+    /// Creates a grid from cells row by row, or `None` if there are the wrong number of cells.
+    pub fn from_cells(size: Size<u32, S>, cells: Vec<T>) -> Option<Self> {
+        if cells.len() == size.width() as usize * size.height() as usize {
+            Some(Grid { size, cells })
+        } else {
+            None
+        }
+    }
+
+    pub fn size(&self) -> Size<u32, S> {
+        self.size
+    }
+
+    pub fn width(&self) -> u32 {
+        self.size.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.size.height()
+    }
+
+    /// The rect covering every cell.
+    pub fn bounds(&self) -> Rect<i32, u32, S> {
+        Rect::new(Point::new(0, 0), self.size)
+    }
+
+    /// The cells row by row.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.cells.chunks(self.width().max(1) as usize)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i32, S>, &T)> + '_ {
+        let width = self.width() as usize;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (Point::new((i % width) as i32, (i / width) as i32), cell))
+    }
+
+    fn index(&self, point: Point<i32, S>) -> Option<usize> {
+        if self.bounds().contains(point) {
+            Some(point.y() as usize * self.width() as usize + point.x() as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, point: Point<i32, S>) -> Option<&T> {
+        self.index(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point<i32, S>) -> Option<&mut T> {
+        self.index(point).map(move |i| &mut self.cells[i])
+    }
+
+    /// Sets the cell at `point`, returning the old value, or `None` if it's outside the grid.
+    pub fn set(&mut self, point: Point<i32, S>, value: T) -> Option<T> {
+        self.get_mut(point)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Sets every cell in `cells` that lies within the grid.
+    pub fn set_all(&mut self, cells: impl IntoIterator<Item = Point<i32, S>>, value: T) {
+        for point in cells {
+            self.set(point, value.clone());
+        }
+    }
+
+    /// A read only view of the part of `rect` within the grid.
+    pub fn view(&self, rect: &Rect<i32, u32, S>) -> GridView<'_, T, S> {
+        GridView {
+            rect: self.clip(rect),
+            grid: self,
+        }
+    }
+
+    /// A view of the part of `rect` within the grid that allows changing cells.
+    pub fn view_mut(&mut self, rect: &Rect<i32, u32, S>) -> GridViewMut<'_, T, S> {
+        GridViewMut {
+            rect: self.clip(rect),
+            grid: self,
+        }
+    }
+
+    fn clip(&self, rect: &Rect<i32, u32, S>) -> Rect<i32, u32, S> {
+        self.bounds()
+            .intersection(rect)
+            .unwrap_or_else(|| Rect::new(Point::new(0, 0), Size::new(0, 0)))
+    }
+
+    /// Copies the part of `rect` within the grid into a new grid.
+    pub fn copy(&self, rect: &Rect<i32, u32, S>) -> Self {
+        self.view(rect).to_grid()
+    }
+
+    /// Pastes `source` with its top left at `at`, dropping cells that land outside the grid.
+    pub fn paste(&mut self, source: &Grid<T, S>, at: Point<i32, S>) {
+        self.paste_where(source, at, |_| true);
+    }
+
+    /// Like [`Grid::paste`], but only pastes cells of `source` for which `filter` is true, such as
+    /// to treat empty cells as transparent.
+    pub fn paste_where(
+        &mut self,
+        source: &Grid<T, S>,
+        at: Point<i32, S>,
+        mut filter: impl FnMut(&T) -> bool,
+    ) {
+        for (point, cell) in source.iter() {
+            if filter(cell) {
+                self.set(point + at, cell.clone());
+            }
+        }
+    }
+
+    /// Sets every cell in `rect` to `value`.
+    pub fn fill_rect(&mut self, rect: &Rect<i32, u32, S>, value: T) {
+        self.view_mut(rect).fill(value);
+    }
+
+    /// Sets the cells along the inside edge of `rect` to `value`.
+    pub fn draw_rect(&mut self, rect: &Rect<i32, u32, S>, value: T) {
+        self.set_all(rasterise_rect_outline(rect), value);
+    }
+
+    /// Sets the cells on the line from `from` to `to` to `value`.
+    pub fn draw_line(&mut self, from: Point<i32, S>, to: Point<i32, S>, value: T) {
+        self.set_all(rasterise_line(from, to), value);
+    }
+
+    /// Changes the size of the grid, keeping cells in place relative to `anchor` and filling new
+    /// cells with `fill`.
+    pub fn resize(&mut self, size: Size<u32, S>, anchor: Anchor, fill: T) {
+        let (dx, dy) = anchor.offsets((self.width(), self.height()), (size.width(), size.height()));
+        let mut resized = Grid::new(size, fill);
+        resized.paste(self, Point::new(dx, dy));
+        *self = resized;
+    }
+
+    /// Replaces the 4-connected region of cells equal to the one at `start` with `value`,
+    /// returning the cells changed.
+    pub fn flood_fill(&mut self, start: Point<i32, S>, value: T) -> Vec<Point<i32, S>>
+    where
+        T: PartialEq,
+    {
+        let target = match self.get(start) {
+            Some(x) if *x != value => x.clone(),
+            _ => return Vec::new(),
+        };
+
+        let mut changed = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(point) = queue.pop_front() {
+            match self.get_mut(point) {
+                Some(cell) if *cell == target => *cell = value.clone(),
+                _ => continue,
+            }
+            changed.push(point);
+            for &(dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+                queue.push_back(point + Point::new(dx, dy));
+            }
+        }
+        changed
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width().max(1) as usize;
+        for row in self.cells.chunks_mut(width) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the grid top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        for y in 0..height / 2 {
+            let (top, bottom) = self.cells.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+
+    pub fn rotate_180(&mut self) {
+        self.cells.reverse();
+    }
+
+    /// Rotates the grid a quarter turn clockwise, swapping its width and height.
+    pub fn rotate_clockwise(&mut self) {
+        let (width, height) = (self.width() as usize, self.height() as usize);
+        // The new row `y` is the old column `y` read bottom to top.
+        let cells = (0..width)
+            .flat_map(|y| (0..height).rev().map(move |x| x * width + y))
+            .map(|i| self.cells[i].clone())
+            .collect();
+        self.size = Size::new(self.height(), self.width());
+        self.cells = cells;
+    }
+
+    /// Rotates the grid a quarter turn counter-clockwise, swapping its width and height.
+    pub fn rotate_counter_clockwise(&mut self) {
+        self.rotate_clockwise();
+        self.rotate_180();
+    }
+}
+
+/// A rect of cells borrowed from a [`Grid`], addressed relative to the rect's top left.
+#[derive(Debug)]
+pub struct GridView<'a, T, S = ScreenTiles> {
+    grid: &'a Grid<T, S>,
+    rect: Rect<i32, u32, S>,
+}
+
+impl<'a, T: Clone, S: Space> GridView<'a, T, S> {
+    /// The part of the grid the view covers, which lies within the grid.
+    pub fn rect(&self) -> Rect<i32, u32, S> {
+        self.rect
+    }
+
+    pub fn size(&self) -> Size<u32, S> {
+        self.rect.size()
+    }
+
+    pub fn get(&self, point: Point<i32, S>) -> Option<&'a T> {
+        let size = Rect::new(Point::new(0, 0), self.rect.size());
+        if size.contains(point) {
+            self.grid.get(point + self.rect.position())
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point<i32, S>, &'a T)> + '_ {
+        let origin = self.rect.position();
+        rasterise_rect(&self.rect)
+            .into_iter()
+            .filter_map(move |point| Some((point - origin, self.grid.get(point)?)))
+    }
+
+    pub fn to_grid(&self) -> Grid<T, S> {
+        let cells = self.iter().map(|(_, cell)| cell.clone()).collect();
+        Grid {
+            size: self.rect.size(),
+            cells,
+        }
+    }
+}
+
+/// A rect of cells mutably borrowed from a [`Grid`], addressed relative to the rect's top left.
+#[derive(Debug)]
+pub struct GridViewMut<'a, T, S = ScreenTiles> {
+    grid: &'a mut Grid<T, S>,
+    rect: Rect<i32, u32, S>,
+}
+
+impl<T: Clone, S: Space> GridViewMut<'_, T, S> {
+    pub fn rect(&self) -> Rect<i32, u32, S> {
+        self.rect
+    }
+
+    pub fn size(&self) -> Size<u32, S> {
+        self.rect.size()
+    }
+
+    fn contains(&self, point: Point<i32, S>) -> bool {
+        Rect::new(Point::new(0, 0), self.rect.size()).contains(point)
+    }
+
+    pub fn get(&self, point: Point<i32, S>) -> Option<&T> {
+        if self.contains(point) {
+            self.grid.get(point + self.rect.position())
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, point: Point<i32, S>) -> Option<&mut T> {
+        if self.contains(point) {
+            self.grid.get_mut(point + self.rect.position())
+        } else {
+            None
+        }
+    }
+
+    /// Sets the cell at `point`, returning the old value, or `None` if it's outside the view.
+    pub fn set(&mut self, point: Point<i32, S>, value: T) -> Option<T> {
+        self.get_mut(point)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for point in rasterise_rect(&self.rect) {
+            self.grid.set(point, value.clone());
+        }
+    }
+}
@@ -1,11 +1,25 @@
+#[cfg(feature = "bevy")]
+mod bevy_interop;
 mod generic;
+mod grid;
 mod point;
+mod polyline;
 mod rect;
 mod size;
+mod space;
+mod spatial;
 
 pub use {
-    generic::{GeomUnit, NonNegativeFloat, NonNegativeGeomUnit},
-    point::{FloatPoint, IntPoint, Point},
+    generic::{GeomUnit, NegativeFloatError, NonNegativeFloat, NonNegativeGeomUnit, PositionUnit},
+    grid::{
+        rasterise_line, rasterise_rect, rasterise_rect_outline, Anchor, Grid, GridView, GridViewMut,
+    },
+    point::{FloatPoint, IntPoint, Point, Rounding},
+    polyline::{NearestPoint, Polyline},
     rect::{FloatRect, IntRect, Rect},
     size::{FloatSize, IntSize, Size},
+    space::{
+        PixelSpace, Pixels, ScreenPixels, ScreenTiles, Space, TileSpace, WorldTiles, TILE_SIZE,
+    },
+    spatial::SpatialIndex,
 };
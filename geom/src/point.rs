@@ -1,22 +1,31 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
 
-use crate::GeomUnit;
+use crate::{GeomUnit, PixelSpace, Pixels, ScreenPixels, Space, TileSpace, TILE_SIZE};
 
-#[derive(Copy, Clone)]
-pub struct Point<T> {
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Point<T, S = Pixels> {
     x: T,
     y: T,
+    space: PhantomData<S>,
 }
 
-impl<T: Debug> Debug for Point<T> {
+impl<T: Debug, S> Debug for Point<T, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({:?}, {:?})", self.x, self.y)
     }
 }
 
-impl<T: GeomUnit> Point<T> {
+impl<T: GeomUnit, S: Space> Point<T, S> {
     pub fn new(x: T, y: T) -> Self {
-        Point { x, y }
+        Point {
+            x,
+            y,
+            space: PhantomData,
+        }
     }
 
     pub fn x(self) -> T {
@@ -28,5 +37,201 @@ impl<T: GeomUnit> Point<T> {
     }
 }
 
+impl<T: GeomUnit + PartialOrd, S: Space> Point<T, S> {
+    /// The smallest coordinates of both points on each axis.
+    pub fn min(self, other: Self) -> Self {
+        Point::new(
+            if other.x < self.x { other.x } else { self.x },
+            if other.y < self.y { other.y } else { self.y },
+        )
+    }
+
+    /// The largest coordinates of both points on each axis.
+    pub fn max(self, other: Self) -> Self {
+        Point::new(
+            if other.x > self.x { other.x } else { self.x },
+            if other.y > self.y { other.y } else { self.y },
+        )
+    }
+}
+
+/// Points double as offsets, so subtracting two gives the offset between them.
+impl<T: GeomUnit + Add<Output = T>, S: Space> Add for Point<T, S> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: GeomUnit + Sub<Output = T>, S: Space> Sub for Point<T, S> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: GeomUnit + Add<Output = T>, S: Space> AddAssign for Point<T, S> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: GeomUnit + Sub<Output = T>, S: Space> SubAssign for Point<T, S> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: GeomUnit + Neg<Output = T>, S: Space> Neg for Point<T, S> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl<T: GeomUnit + Mul<Output = T>, S: Space> Mul<T> for Point<T, S> {
+    type Output = Self;
+
+    fn mul(self, scale: T) -> Self {
+        Point::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl<S: Space> Point<i32, S> {
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Point::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Point::new(
+            self.x.checked_sub(other.x)?,
+            self.y.checked_sub(other.y)?,
+        ))
+    }
+
+    pub fn checked_mul(self, scale: i32) -> Option<Self> {
+        Some(Point::new(
+            self.x.checked_mul(scale)?,
+            self.y.checked_mul(scale)?,
+        ))
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Point::new(
+            self.x.saturating_add(other.x),
+            self.y.saturating_add(other.y),
+        )
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Point::new(
+            self.x.saturating_sub(other.x),
+            self.y.saturating_sub(other.y),
+        )
+    }
+
+    pub fn saturating_mul(self, scale: i32) -> Self {
+        Point::new(self.x.saturating_mul(scale), self.y.saturating_mul(scale))
+    }
+}
+
+/// How to round floating point coordinates to integers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    /// Rounds halfway cases away from zero.
+    Nearest,
+    TowardZero,
+}
+
+impl Rounding {
+    /// Rounds `x`, saturating at the bounds of `i32` and mapping NaN to zero.
+    pub fn round(self, x: f32) -> i32 {
+        let x = match self {
+            Rounding::Floor => x.floor(),
+            Rounding::Ceil => x.ceil(),
+            Rounding::Nearest => x.round(),
+            Rounding::TowardZero => x.trunc(),
+        };
+        x as i32
+    }
+
+    /// Divides `x` by a positive `divisor`, rounding the exact quotient.
+    pub fn divide(self, x: i32, divisor: i32) -> i32 {
+        let (x, divisor) = (x as i64, divisor as i64);
+        let (quotient, remainder) = (x.div_euclid(divisor), x.rem_euclid(divisor));
+        let round_up = match self {
+            Rounding::Floor => false,
+            Rounding::Ceil => remainder > 0,
+            Rounding::Nearest => remainder * 2 > divisor || (remainder * 2 == divisor && x >= 0),
+            Rounding::TowardZero => remainder > 0 && x < 0,
+        };
+        (quotient + round_up as i64) as i32
+    }
+
+    /// Divides `x` by a positive `divisor` like [`Rounding::divide`], for values too large for
+    /// an `i32`.
+    pub fn divide_unsigned(self, x: u32, divisor: u32) -> u32 {
+        let (quotient, remainder) = (x / divisor, x % divisor);
+        let round_up = match self {
+            Rounding::Floor | Rounding::TowardZero => false,
+            Rounding::Ceil => remainder > 0,
+            Rounding::Nearest => remainder > 0 && remainder >= divisor - remainder,
+        };
+        quotient + round_up as u32
+    }
+}
+
+impl<S: Space> Point<f32, S> {
+    pub fn round(self, rounding: Rounding) -> Point<i32, S> {
+        Point::new(rounding.round(self.x), rounding.round(self.y))
+    }
+}
+
+impl<S: Space> From<Point<i32, S>> for Point<f32, S> {
+    fn from(point: Point<i32, S>) -> Self {
+        Point::new(point.x as f32, point.y as f32)
+    }
+}
+
+impl<S: TileSpace> Point<i32, S> {
+    /// The top left pixel of the tile, saturating at the bounds of `i32`.
+    pub fn to_pixels(self) -> Point<i32, S::Pixels> {
+        let scale = |x: i32| x.saturating_mul(TILE_SIZE as i32);
+        Point::new(scale(self.x), scale(self.y))
+    }
+}
+
+impl<S: PixelSpace> Point<i32, S> {
+    /// The tile containing the pixel with `Rounding::Floor`.
+    pub fn to_tiles(self, rounding: Rounding) -> Point<i32, S::Tiles> {
+        Point::new(
+            rounding.divide(self.x, TILE_SIZE as i32),
+            rounding.divide(self.y, TILE_SIZE as i32),
+        )
+    }
+}
+
+impl<T: GeomUnit + Add<Output = T>> Point<T, ScreenPixels> {
+    /// Converts from relative to the screen at `origin` to world pixels.
+    pub fn to_world(self, origin: Point<T, Pixels>) -> Point<T, Pixels> {
+        Point::new(self.x + origin.x, self.y + origin.y)
+    }
+}
+
+impl<T: GeomUnit + Sub<Output = T>> Point<T, Pixels> {
+    /// Converts from world pixels to relative to the screen at `origin`.
+    pub fn to_screen(self, origin: Point<T, Pixels>) -> Point<T, ScreenPixels> {
+        Point::new(self.x - origin.x, self.y - origin.y)
+    }
+}
+
 pub type IntPoint = Point<i32>;
 pub type FloatPoint = Point<f32>;
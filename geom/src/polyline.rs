@@ -0,0 +1,302 @@
+use std::iter::FromIterator;
+
+use crate::{NonNegativeFloat, Pixels, Point, Rect, Space};
+
+/// The most points [`Polyline::sample_every`] gives, besides the line's end.
+const MAX_SAMPLES: usize = 4096;
+
+/// The closest point on a [`Polyline`] to some other point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NearestPoint<S = Pixels> {
+    pub point: Point<f32, S>,
+    /// Index of the segment the point lies on, which is zero for a single point line.
+    pub segment: usize,
+    /// Distance along the line from its start to the point.
+    pub along: NonNegativeFloat,
+    /// Distance from the other point to the point.
+    pub distance: NonNegativeFloat,
+}
+
+/// Straight segments joining points in order, like the path of a moving platform.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Polyline<S = Pixels> {
+    points: Vec<Point<f32, S>>,
+}
+
+fn distance<S: Space>(a: Point<f32, S>, b: Point<f32, S>) -> f32 {
+    (b.x() - a.x()).hypot(b.y() - a.y())
+}
+
+fn lerp<S: Space>(a: Point<f32, S>, b: Point<f32, S>, t: f32) -> Point<f32, S> {
+    Point::new(a.x() + (b.x() - a.x()) * t, a.y() + (b.y() - a.y()) * t)
+}
+
+/// The fraction along the segment from `a` to `b` closest to `point`.
+fn project<S: Space>(a: Point<f32, S>, b: Point<f32, S>, point: Point<f32, S>) -> f32 {
+    let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return 0.0;
+    }
+    let t = ((point.x() - a.x()) * dx + (point.y() - a.y()) * dy) / length_squared;
+    t.clamp(0.0, 1.0)
+}
+
+impl<S: Space> Polyline<S> {
+    pub fn new(points: Vec<Point<f32, S>>) -> Self {
+        Polyline { points }
+    }
+
+    pub fn points(&self) -> &[Point<f32, S>] {
+        &self.points
+    }
+
+    pub fn points_mut(&mut self) -> &mut Vec<Point<f32, S>> {
+        &mut self.points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Each segment's start and end, in order.
+    pub fn segments(&self) -> impl Iterator<Item = (Point<f32, S>, Point<f32, S>)> + '_ {
+        self.points.windows(2).map(|x| (x[0], x[1]))
+    }
+
+    pub fn length(&self) -> NonNegativeFloat {
+        NonNegativeFloat::clamped(self.segments().map(|(a, b)| distance(a, b)).sum())
+    }
+
+    /// The point `along` the line from its start, clamped to the ends, or `None` if the line has
+    /// no points.
+    pub fn sample(&self, along: f32) -> Option<Point<f32, S>> {
+        let mut remaining = along.max(0.0);
+        for (a, b) in self.segments() {
+            let length = distance(a, b);
+            if remaining <= length {
+                let t = if length > 0.0 {
+                    remaining / length
+                } else {
+                    0.0
+                };
+                return Some(lerp(a, b, t));
+            }
+            remaining -= length;
+        }
+        self.points.last().copied()
+    }
+
+    /// Points every `spacing` along the line, starting at its start and ending at its end.
+    ///
+    /// A spacing that would give more than 4096 points is widened to give that many, so a tiny
+    /// spacing can't exhaust memory.
+    pub fn sample_every(&self, spacing: NonNegativeFloat) -> Vec<Point<f32, S>> {
+        let (length, spacing) = (self.length().get(), spacing.get());
+        if self.is_empty() || spacing == 0.0 || !length.is_finite() {
+            return self.points.clone();
+        }
+        let spacing = spacing.max(length / MAX_SAMPLES as f32);
+        let count = ((length / spacing).floor() as usize).min(MAX_SAMPLES);
+
+        // Walk the segments once, as each sample lies at or past the previous one.
+        let mut samples = Vec::with_capacity(count + 2);
+        let mut segments = self.segments().peekable();
+        let mut start = 0.0;
+        for i in 0..=count {
+            let along = i as f32 * spacing;
+            let sample = loop {
+                let (a, b) = match segments.peek() {
+                    Some(&x) => x,
+                    None => break None,
+                };
+                let length = distance(a, b);
+                if along - start <= length {
+                    let t = if length > 0.0 {
+                        (along - start) / length
+                    } else {
+                        0.0
+                    };
+                    break Some(lerp(a, b, t));
+                }
+                start += length;
+                segments.next();
+            };
+            match sample {
+                Some(x) => samples.push(x),
+                // Past the last segment through rounding, or the line is a single point.
+                None => break,
+            }
+        }
+        if samples.len() <= count || (count as f32) * spacing < length {
+            samples.extend(self.points.last().copied());
+        }
+        samples
+    }
+
+    /// The closest point on the line to `point`, or `None` if the line has no points.
+    pub fn nearest_point(&self, point: Point<f32, S>) -> Option<NearestPoint<S>> {
+        let first = *self.points.first()?;
+        let mut best = NearestPoint {
+            point: first,
+            segment: 0,
+            along: NonNegativeFloat::ZERO,
+            distance: NonNegativeFloat::clamped(distance(first, point)),
+        };
+
+        let mut start = 0.0;
+        for (segment, (a, b)) in self.segments().enumerate() {
+            let t = project(a, b, point);
+            let nearest = lerp(a, b, t);
+            let length = distance(a, b);
+            let candidate = NearestPoint {
+                point: nearest,
+                segment,
+                along: NonNegativeFloat::clamped(start + length * t),
+                distance: NonNegativeFloat::clamped(distance(nearest, point)),
+            };
+            if candidate.distance < best.distance {
+                best = candidate;
+            }
+            start += length;
+        }
+        Some(best)
+    }
+
+    /// The index of the segment closest to `point`, if within `tolerance` of it.
+    pub fn hit_segment(&self, point: Point<f32, S>, tolerance: NonNegativeFloat) -> Option<usize> {
+        if self.points.len() < 2 {
+            return None;
+        }
+        self.nearest_point(point)
+            .filter(|x| x.distance <= tolerance)
+            .map(|x| x.segment)
+    }
+
+    /// The smallest rect whose edges touch every point, or `None` if the line has no points.
+    pub fn bounding_box(&self) -> Option<Rect<f32, NonNegativeFloat, S>> {
+        let first = self.points.first()?;
+        let (mut left, mut top, mut right, mut bottom) =
+            (first.x(), first.y(), first.x(), first.y());
+        for point in self.points.iter() {
+            left = left.min(point.x());
+            top = top.min(point.y());
+            right = right.max(point.x());
+            bottom = bottom.max(point.y());
+        }
+        Some(Rect::from_edges(left, top, right, bottom))
+    }
+}
+
+impl<S: Space> FromIterator<Point<f32, S>> for Polyline<S> {
+    fn from_iter<I: IntoIterator<Item = Point<f32, S>>>(iter: I) -> Self {
+        Polyline::new(iter.into_iter().collect())
+    }
+}
+
+impl<S: Space> FromIterator<Point<i32, S>> for Polyline<S> {
+    fn from_iter<I: IntoIterator<Item = Point<i32, S>>>(iter: I) -> Self {
+        Polyline::new(iter.into_iter().map(Point::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(points: &[(f32, f32)]) -> Polyline {
+        points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn spacing(x: f32) -> NonNegativeFloat {
+        NonNegativeFloat::clamped(x)
+    }
+
+    fn coords(points: &[Point<f32>]) -> Vec<(f32, f32)> {
+        points.iter().map(|x| (x.x(), x.y())).collect()
+    }
+
+    #[test]
+    fn length_and_sample() {
+        let line = line(&[(0.0, 0.0), (3.0, 4.0), (3.0, 10.0)]);
+        assert_eq!(line.length().get(), 11.0);
+        assert_eq!(line.sample(-1.0), Some(Point::new(0.0, 0.0)));
+        assert_eq!(line.sample(2.5), Some(Point::new(1.5, 2.0)));
+        assert_eq!(line.sample(7.0), Some(Point::new(3.0, 6.0)));
+        assert_eq!(line.sample(20.0), Some(Point::new(3.0, 10.0)));
+        assert_eq!(Polyline::<Pixels>::new(Vec::new()).sample(1.0), None);
+    }
+
+    #[test]
+    fn sample_every_includes_both_ends() {
+        let line = line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 5.0)]);
+        assert_eq!(
+            coords(&line.sample_every(spacing(4.0))),
+            vec![(0.0, 0.0), (4.0, 0.0), (8.0, 0.0), (10.0, 2.0), (10.0, 5.0)]
+        );
+        assert_eq!(
+            coords(&line.sample_every(spacing(5.0))),
+            vec![(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (10.0, 5.0)]
+        );
+    }
+
+    #[test]
+    fn sample_every_degenerate_lines() {
+        assert!(Polyline::<Pixels>::new(Vec::new())
+            .sample_every(spacing(1.0))
+            .is_empty());
+        let point = line(&[(2.0, 3.0)]);
+        assert_eq!(coords(&point.sample_every(spacing(1.0))), vec![(2.0, 3.0)]);
+        let repeated = line(&[(2.0, 3.0), (2.0, 3.0), (4.0, 3.0)]);
+        assert_eq!(
+            coords(&repeated.sample_every(spacing(1.0))),
+            vec![(2.0, 3.0), (3.0, 3.0), (4.0, 3.0)]
+        );
+        let line = line(&[(0.0, 0.0), (1.0, 0.0)]);
+        assert_eq!(line.sample_every(spacing(0.0)), line.points());
+        assert_eq!(
+            coords(&line.sample_every(spacing(5.0))),
+            vec![(0.0, 0.0), (1.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn sample_every_bounds_count() {
+        let line = line(&[(0.0, 0.0), (1.0e6, 0.0), (1.0e6, 1.0e6)]);
+        let samples = line.sample_every(spacing(1.0e-6));
+        assert!(samples.len() <= MAX_SAMPLES + 2);
+        assert_eq!(samples.first(), Some(&Point::new(0.0, 0.0)));
+        assert_eq!(samples.last(), Some(&Point::new(1.0e6, 1.0e6)));
+    }
+
+    #[test]
+    fn nearest_point_and_hit_segment() {
+        let line = line(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let nearest = line.nearest_point(Point::new(12.0, 4.0)).unwrap();
+        assert_eq!(nearest.point, Point::new(10.0, 4.0));
+        assert_eq!(nearest.segment, 1);
+        assert_eq!(nearest.along.get(), 14.0);
+        assert_eq!(nearest.distance.get(), 2.0);
+
+        assert_eq!(
+            line.hit_segment(Point::new(5.0, 1.0), spacing(1.0)),
+            Some(0)
+        );
+        assert_eq!(line.hit_segment(Point::new(5.0, 3.0), spacing(1.0)), None);
+        assert_eq!(
+            self::line(&[(1.0, 1.0)]).hit_segment(Point::new(1.0, 1.0), spacing(1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn bounding_box() {
+        let line = line(&[(3.0, -1.0), (-2.0, 4.0), (1.0, 2.0)]);
+        let rect = line.bounding_box().unwrap();
+        assert_eq!(
+            (rect.left(), rect.top(), rect.right(), rect.bottom()),
+            (-2.0, -1.0, 3.0, 4.0)
+        );
+        assert!(Polyline::<Pixels>::new(Vec::new()).bounding_box().is_none());
+    }
+}
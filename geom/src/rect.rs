@@ -1,30 +1,448 @@
 use std::fmt::Debug;
 
-use crate::{GeomUnit, NonNegativeFloat, NonNegativeGeomUnit, Point, Size};
+use crate::{
+    GeomUnit, NonNegativeFloat, NonNegativeGeomUnit, PixelSpace, Pixels, Point, PositionUnit,
+    Rounding, ScreenPixels, Size, Space, TileSpace,
+};
 
-#[derive(Debug, Copy, Clone)]
-pub struct Rect<T, U> {
-    position: Point<T>,
-    size: Size<U>,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rect<T, U, S = Pixels> {
+    position: Point<T, S>,
+    size: Size<U, S>,
 }
 
-impl<T: GeomUnit, U: NonNegativeGeomUnit> Rect<T, U> {
-    pub fn new(position: Point<T>, size: Size<U>) -> Self {
+impl<T: GeomUnit, U: NonNegativeGeomUnit, S: Space> Rect<T, U, S> {
+    pub fn new(position: Point<T, S>, size: Size<U, S>) -> Self {
         Rect { position, size }
     }
 }
 
-impl<T: Copy, U> Rect<T, U> {
-    pub fn position(&self) -> Point<T> {
+impl<T: Copy, U, S: Copy> Rect<T, U, S> {
+    pub fn position(&self) -> Point<T, S> {
         self.position
     }
 }
 
-impl<T, U: Copy> Rect<T, U> {
-    pub fn size(&self) -> Size<U> {
+impl<T, U: Copy, S: Copy> Rect<T, U, S> {
+    pub fn size(&self) -> Size<U, S> {
         self.size
     }
 }
 
+/// Rects cover the half open ranges `left..right` and `top..bottom`, with `y` increasing downwards.
+impl<T: PositionUnit<Size = U>, U: NonNegativeGeomUnit, S: Space> Rect<T, U, S> {
+    /// Creates the rect between two edges on each axis, which is empty if they are reversed.
+    pub fn from_edges(left: T, top: T, right: T, bottom: T) -> Self {
+        Rect::new(
+            Point::new(left, top),
+            Size::new(left.distance_to(right), top.distance_to(bottom)),
+        )
+    }
+
+    /// The smallest rect containing every rect in `rects`, or `None` if there are none.
+    pub fn bounding_box(rects: impl IntoIterator<Item = Self>) -> Option<Self> {
+        rects.into_iter().reduce(|a, b| a.union(&b))
+    }
+
+    pub fn left(&self) -> T {
+        self.position.x()
+    }
+
+    pub fn top(&self) -> T {
+        self.position.y()
+    }
+
+    pub fn right(&self) -> T {
+        self.position.x().offset(self.size.width())
+    }
+
+    pub fn bottom(&self) -> T {
+        self.position.y().offset(self.size.height())
+    }
+
+    pub fn top_left(&self) -> Point<T, S> {
+        self.position
+    }
+
+    pub fn top_right(&self) -> Point<T, S> {
+        Point::new(self.right(), self.top())
+    }
+
+    pub fn bottom_left(&self) -> Point<T, S> {
+        Point::new(self.left(), self.bottom())
+    }
+
+    pub fn bottom_right(&self) -> Point<T, S> {
+        Point::new(self.right(), self.bottom())
+    }
+
+    /// Corners clockwise from the top left.
+    pub fn corners(&self) -> [Point<T, S>; 4] {
+        [
+            self.top_left(),
+            self.top_right(),
+            self.bottom_right(),
+            self.bottom_left(),
+        ]
+    }
+
+    pub fn center(&self) -> Point<T, S> {
+        Point::new(
+            self.left().midpoint(self.right()),
+            self.top().midpoint(self.bottom()),
+        )
+    }
+
+    /// Whether the rect covers no area.
+    pub fn is_empty(&self) -> bool {
+        !(self.left() < self.right() && self.top() < self.bottom())
+    }
+
+    pub fn contains(&self, point: Point<T, S>) -> bool {
+        self.left() <= point.x()
+            && point.x() < self.right()
+            && self.top() <= point.y()
+            && point.y() < self.bottom()
+    }
+
+    /// Whether `other` lies entirely within the rect, which is always true of empty rects.
+    pub fn contains_rect(&self, other: &Self) -> bool {
+        other.is_empty()
+            || (self.left() <= other.left()
+                && other.right() <= self.right()
+                && self.top() <= other.top()
+                && other.bottom() <= self.bottom())
+    }
+
+    /// Whether the rects share any area, so rects that only touch don't intersect.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.intersects(other) {
+            Some(Rect::from_edges(
+                max(self.left(), other.left()),
+                max(self.top(), other.top()),
+                min(self.right(), other.right()),
+                min(self.bottom(), other.bottom()),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The smallest rect containing both rects.
+    pub fn union(&self, other: &Self) -> Self {
+        Rect::from_edges(
+            min(self.left(), other.left()),
+            min(self.top(), other.top()),
+            max(self.right(), other.right()),
+            max(self.bottom(), other.bottom()),
+        )
+    }
+
+    /// Moves the rect by `offset`, stopping at the edge of the unit's range rather than
+    /// overflowing.
+    pub fn translate(&self, offset: Point<T, S>) -> Self {
+        let position = Point::new(
+            self.left().saturating_add(offset.x()),
+            self.top().saturating_add(offset.y()),
+        );
+        Rect::new(position, self.size)
+    }
+
+    /// Grows each edge outwards by `dx` and `dy`, or inwards if they are negative. A rect shrunk
+    /// past nothing collapses to an empty rect at its center, and edges stop at the edge of the
+    /// unit's range.
+    pub fn inflate(&self, dx: T, dy: T) -> Self {
+        let center = self.center();
+        let (mut left, mut right) = (
+            self.left().saturating_sub(dx),
+            self.right().saturating_add(dx),
+        );
+        let (mut top, mut bottom) = (
+            self.top().saturating_sub(dy),
+            self.bottom().saturating_add(dy),
+        );
+        if right < left {
+            left = center.x();
+            right = center.x();
+        }
+        if bottom < top {
+            top = center.y();
+            bottom = center.y();
+        }
+        Rect::from_edges(left, top, right, bottom)
+    }
+}
+
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+impl<S: TileSpace> Rect<i32, u32, S> {
+    pub fn to_pixels(self) -> Rect<i32, u32, S::Pixels> {
+        Rect::new(self.position.to_pixels(), self.size.to_pixels())
+    }
+}
+
+impl<S: PixelSpace> Rect<i32, u32, S> {
+    /// The tiles covering any part of the rect.
+    pub fn to_covering_tiles(self) -> Rect<i32, u32, S::Tiles> {
+        let top_left = self.top_left().to_tiles(Rounding::Floor);
+        let bottom_right = self.bottom_right().to_tiles(Rounding::Ceil);
+        Rect::from_edges(
+            top_left.x(),
+            top_left.y(),
+            bottom_right.x(),
+            bottom_right.y(),
+        )
+    }
+}
+
+impl<T: PositionUnit<Size = U>, U: NonNegativeGeomUnit> Rect<T, U, ScreenPixels> {
+    /// Converts from relative to the screen at `origin` to world pixels.
+    pub fn to_world(self, origin: Point<T, Pixels>) -> Rect<T, U, Pixels> {
+        Rect::new(self.position.to_world(origin), self.size.to_world())
+    }
+}
+
+impl<T: PositionUnit<Size = U>, U: NonNegativeGeomUnit> Rect<T, U, Pixels> {
+    /// Converts from world pixels to relative to the screen at `origin`.
+    pub fn to_screen(self, origin: Point<T, Pixels>) -> Rect<T, U, ScreenPixels> {
+        Rect::new(self.position.to_screen(origin), self.size.to_screen())
+    }
+}
+
 pub type IntRect = Rect<i32, u32>;
 pub type FloatRect = Rect<f32, NonNegativeFloat>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WorldTiles;
+
+    fn int(left: i32, top: i32, right: i32, bottom: i32) -> IntRect {
+        Rect::from_edges(left, top, right, bottom)
+    }
+
+    fn float(left: f32, top: f32, right: f32, bottom: f32) -> FloatRect {
+        Rect::from_edges(left, top, right, bottom)
+    }
+
+    #[test]
+    fn contains_is_half_open() {
+        let rect = int(0, 0, 10, 5);
+        assert!(rect.contains(Point::new(0, 0)));
+        assert!(rect.contains(Point::new(9, 4)));
+        assert!(!rect.contains(Point::new(10, 0)));
+        assert!(!rect.contains(Point::new(0, 5)));
+        assert!(!rect.contains(Point::new(-1, 2)));
+
+        let rect = float(0.0, 0.0, 1.0, 1.0);
+        assert!(rect.contains(Point::new(0.0, 0.5)));
+        assert!(rect.contains(Point::new(0.999, 0.999)));
+        assert!(!rect.contains(Point::new(1.0, 0.5)));
+    }
+
+    #[test]
+    fn empty_rects_contain_nothing() {
+        let empty = int(3, 3, 3, 8);
+        assert!(empty.is_empty());
+        assert!(!empty.contains(Point::new(3, 4)));
+        assert!(int(0, 0, 10, 10).contains_rect(&empty));
+        assert!(int(5, 5, 2, 2).is_empty());
+    }
+
+    #[test]
+    fn contains_rect() {
+        let rect = int(0, 0, 10, 10);
+        assert!(rect.contains_rect(&int(0, 0, 10, 10)));
+        assert!(rect.contains_rect(&int(2, 2, 5, 5)));
+        assert!(!rect.contains_rect(&int(5, 5, 11, 6)));
+        assert!(float(0.0, 0.0, 2.0, 2.0).contains_rect(&float(0.5, 0.5, 2.0, 1.0)));
+    }
+
+    #[test]
+    fn intersects_and_intersection() {
+        let a = int(0, 0, 10, 10);
+        let b = int(5, -5, 15, 5);
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(int(5, 0, 10, 5)));
+        assert_eq!(b.intersection(&a), Some(int(5, 0, 10, 5)));
+
+        let inside = int(2, 2, 4, 4);
+        assert_eq!(a.intersection(&inside), Some(inside));
+
+        let a = float(0.0, 0.0, 2.0, 2.0);
+        let b = float(1.0, 1.5, 3.0, 4.0);
+        assert_eq!(a.intersection(&b), Some(float(1.0, 1.5, 2.0, 2.0)));
+    }
+
+    #[test]
+    fn disjoint_rects_dont_intersect() {
+        let a = int(0, 0, 10, 10);
+        let b = int(20, 20, 30, 30);
+        assert!(!a.intersects(&b));
+        assert_eq!(a.intersection(&b), None);
+
+        let a = float(0.0, 0.0, 1.0, 1.0);
+        let b = float(-3.0, 0.0, -2.0, 1.0);
+        assert!(!a.intersects(&b));
+        assert_eq!(a.intersection(&b), None);
+    }
+
+    #[test]
+    fn touching_rects_dont_intersect() {
+        let a = int(0, 0, 10, 10);
+        assert!(!a.intersects(&int(10, 0, 20, 10)));
+        assert!(!a.intersects(&int(0, 10, 10, 20)));
+        assert!(!a.intersects(&int(10, 10, 20, 20)));
+        assert_eq!(a.intersection(&int(10, 0, 20, 10)), None);
+
+        let a = float(0.0, 0.0, 1.0, 1.0);
+        assert!(!a.intersects(&float(1.0, 0.0, 2.0, 1.0)));
+        assert_eq!(a.intersection(&float(0.0, 1.0, 1.0, 2.0)), None);
+    }
+
+    #[test]
+    fn union_and_bounding_box() {
+        let a = int(0, 0, 10, 10);
+        let b = int(20, -5, 30, 5);
+        assert_eq!(a.union(&b), int(0, -5, 30, 10));
+        assert_eq!(b.union(&a), int(0, -5, 30, 10));
+        assert_eq!(
+            Rect::bounding_box(vec![a, b, int(-1, 3, 2, 40)]),
+            Some(int(-1, -5, 30, 40))
+        );
+        assert_eq!(IntRect::bounding_box(Vec::new()), None);
+
+        let a = float(0.0, 0.0, 1.0, 1.0);
+        let b = float(2.0, 2.0, 3.5, 3.0);
+        assert_eq!(a.union(&b), float(0.0, 0.0, 3.5, 3.0));
+        assert_eq!(Rect::bounding_box(vec![b]), Some(b));
+    }
+
+    #[test]
+    fn inflate() {
+        let rect = int(0, 0, 10, 10);
+        assert_eq!(rect.inflate(2, 3), int(-2, -3, 12, 13));
+        assert_eq!(rect.inflate(-2, -1), int(2, 1, 8, 9));
+        assert_eq!(rect.inflate(-5, 0), int(5, 0, 5, 10));
+
+        let rect = float(0.0, 0.0, 2.0, 4.0);
+        assert_eq!(rect.inflate(0.5, 1.0), float(-0.5, -1.0, 2.5, 5.0));
+        assert_eq!(rect.inflate(-0.5, -1.0), float(0.5, 1.0, 1.5, 3.0));
+    }
+
+    #[test]
+    fn inflate_past_nothing_collapses_to_center() {
+        let rect = int(0, 0, 10, 20);
+        let shrunk = rect.inflate(-8, -15);
+        assert!(shrunk.is_empty());
+        assert_eq!(shrunk, int(5, 10, 5, 10));
+
+        let shrunk = int(0, 0, 10, 20).inflate(-8, 1);
+        assert_eq!(shrunk, int(5, -1, 5, 21));
+
+        let shrunk = float(0.0, 0.0, 2.0, 2.0).inflate(-3.0, -3.0);
+        assert!(shrunk.is_empty());
+        assert_eq!(shrunk.center(), Point::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn inflate_saturates() {
+        let rect = int(i32::MAX - 10, i32::MIN + 10, i32::MAX - 5, i32::MIN + 20);
+        assert_eq!(
+            rect.inflate(100, 100),
+            int(i32::MAX - 110, i32::MIN, i32::MAX, i32::MIN + 120)
+        );
+    }
+
+    #[test]
+    fn translate() {
+        let rect = int(0, 0, 10, 5);
+        assert_eq!(rect.translate(Point::new(3, -4)), int(3, -4, 13, 1));
+        assert_eq!(rect.translate(Point::new(0, 0)), rect);
+
+        let rect = float(1.0, 1.0, 2.0, 3.0);
+        assert_eq!(
+            rect.translate(Point::new(-1.0, 0.5)),
+            float(0.0, 1.5, 1.0, 3.5)
+        );
+    }
+
+    #[test]
+    fn translate_saturates() {
+        let rect = int(i32::MAX - 10, 0, i32::MAX, 10);
+        let moved = rect.translate(Point::new(100, i32::MIN));
+        assert_eq!(moved.left(), i32::MAX);
+        assert_eq!(moved.right(), i32::MAX);
+        assert_eq!(moved.top(), i32::MIN);
+        assert_eq!(moved.size(), rect.size());
+    }
+
+    #[test]
+    fn corners_edges_and_center() {
+        let rect = int(-2, 1, 6, 5);
+        assert_eq!(
+            (rect.left(), rect.top(), rect.right(), rect.bottom()),
+            (-2, 1, 6, 5)
+        );
+        assert_eq!(
+            rect.corners(),
+            [
+                Point::new(-2, 1),
+                Point::new(6, 1),
+                Point::new(6, 5),
+                Point::new(-2, 5)
+            ]
+        );
+        assert_eq!(rect.center(), Point::new(2, 3));
+        // Odd sizes round the centre down, towards negative infinity.
+        assert_eq!(int(-3, -3, 0, 0).center(), Point::new(-2, -2));
+
+        let rect = float(0.0, 1.0, 3.0, 2.0);
+        assert_eq!(rect.top_left(), Point::new(0.0, 1.0));
+        assert_eq!(rect.top_right(), Point::new(3.0, 1.0));
+        assert_eq!(rect.bottom_right(), Point::new(3.0, 2.0));
+        assert_eq!(rect.bottom_left(), Point::new(0.0, 2.0));
+        assert_eq!(rect.center(), Point::new(1.5, 1.5));
+    }
+
+    #[test]
+    fn tile_conversions_dont_overflow() {
+        let tiles: Rect<i32, u32, WorldTiles> =
+            Rect::new(Point::new(i32::MAX, i32::MIN), Size::new(u32::MAX, 3));
+        let pixels = tiles.to_pixels();
+        assert_eq!(pixels.position(), Point::new(i32::MAX, i32::MIN));
+        assert_eq!(pixels.size(), Size::new(u32::MAX, 24));
+
+        let size: Size<u32, Pixels> = Size::new(u32::MAX, 17);
+        assert_eq!(size.to_tiles(Rounding::Floor), Size::new(u32::MAX / 8, 2));
+        assert_eq!(
+            size.to_tiles(Rounding::Ceil),
+            Size::new(u32::MAX / 8 + 1, 3)
+        );
+        assert_eq!(
+            size.to_tiles(Rounding::Nearest),
+            Size::new(u32::MAX / 8 + 1, 2)
+        );
+    }
+}
@@ -1,24 +1,33 @@
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Add, AddAssign, Mul, Sub, SubAssign},
+};
 
-use crate::{NonNegativeFloat, NonNegativeGeomUnit};
+use crate::{
+    NonNegativeFloat, NonNegativeGeomUnit, PixelSpace, Pixels, Rounding, ScreenPixels, Space,
+    TileSpace, TILE_SIZE,
+};
 
-#[derive(Copy, Clone)]
-pub struct Size<T> {
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Size<T, S = Pixels> {
     width: T,
     height: T,
+    space: PhantomData<S>,
 }
 
-impl<T: Debug> Debug for Size<T> {
+impl<T: Debug, S> Debug for Size<T, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}x{:?}", self.width, self.height)
     }
 }
 
-impl<T: NonNegativeGeomUnit> Size<T> {
+impl<T: NonNegativeGeomUnit, S: Space> Size<T, S> {
     pub fn new(x: T, y: T) -> Self {
         Size {
             width: x,
             height: y,
+            space: PhantomData,
         }
     }
 
@@ -31,5 +40,149 @@ impl<T: NonNegativeGeomUnit> Size<T> {
     }
 }
 
+impl<T: NonNegativeGeomUnit + PartialOrd, S: Space> Size<T, S> {
+    /// The smallest extent of both sizes on each axis.
+    pub fn min(self, other: Self) -> Self {
+        Size::new(
+            if other.width < self.width {
+                other.width
+            } else {
+                self.width
+            },
+            if other.height < self.height {
+                other.height
+            } else {
+                self.height
+            },
+        )
+    }
+
+    /// The largest extent of both sizes on each axis.
+    pub fn max(self, other: Self) -> Self {
+        Size::new(
+            if other.width > self.width {
+                other.width
+            } else {
+                self.width
+            },
+            if other.height > self.height {
+                other.height
+            } else {
+                self.height
+            },
+        )
+    }
+}
+
+impl<T: NonNegativeGeomUnit + Add<Output = T>, S: Space> Add for Size<T, S> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Size::new(self.width + other.width, self.height + other.height)
+    }
+}
+
+/// Panics on underflow like the unit's own subtraction, see the saturating and checked variants.
+impl<T: NonNegativeGeomUnit + Sub<Output = T>, S: Space> Sub for Size<T, S> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Size::new(self.width - other.width, self.height - other.height)
+    }
+}
+
+impl<T: NonNegativeGeomUnit + Add<Output = T>, S: Space> AddAssign for Size<T, S> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: NonNegativeGeomUnit + Sub<Output = T>, S: Space> SubAssign for Size<T, S> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: NonNegativeGeomUnit + Mul<Output = T>, S: Space> Mul<T> for Size<T, S> {
+    type Output = Self;
+
+    fn mul(self, scale: T) -> Self {
+        Size::new(self.width * scale, self.height * scale)
+    }
+}
+
+impl<S: Space> Size<u32, S> {
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Size::new(
+            self.width.checked_add(other.width)?,
+            self.height.checked_add(other.height)?,
+        ))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        Some(Size::new(
+            self.width.checked_sub(other.width)?,
+            self.height.checked_sub(other.height)?,
+        ))
+    }
+
+    pub fn checked_mul(self, scale: u32) -> Option<Self> {
+        Some(Size::new(
+            self.width.checked_mul(scale)?,
+            self.height.checked_mul(scale)?,
+        ))
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Size::new(
+            self.width.saturating_add(other.width),
+            self.height.saturating_add(other.height),
+        )
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Size::new(
+            self.width.saturating_sub(other.width),
+            self.height.saturating_sub(other.height),
+        )
+    }
+
+    pub fn saturating_mul(self, scale: u32) -> Self {
+        Size::new(
+            self.width.saturating_mul(scale),
+            self.height.saturating_mul(scale),
+        )
+    }
+}
+
+impl<S: TileSpace> Size<u32, S> {
+    /// The size in pixels, saturating at `u32::MAX`.
+    pub fn to_pixels(self) -> Size<u32, S::Pixels> {
+        let scale = |x: u32| x.saturating_mul(TILE_SIZE);
+        Size::new(scale(self.width), scale(self.height))
+    }
+}
+
+impl<S: PixelSpace> Size<u32, S> {
+    /// The number of tiles the size spans, with partial tiles rounded by `rounding`.
+    pub fn to_tiles(self, rounding: Rounding) -> Size<u32, S::Tiles> {
+        let divide = |x: u32| rounding.divide_unsigned(x, TILE_SIZE);
+        Size::new(divide(self.width), divide(self.height))
+    }
+}
+
+/// Sizes don't depend on an origin, so convert freely between world and screen pixels.
+impl<T: NonNegativeGeomUnit> Size<T, ScreenPixels> {
+    pub fn to_world(self) -> Size<T, Pixels> {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl<T: NonNegativeGeomUnit> Size<T, Pixels> {
+    pub fn to_screen(self) -> Size<T, ScreenPixels> {
+        Size::new(self.width, self.height)
+    }
+}
+
 pub type IntSize = Size<u32>;
 pub type FloatSize = Size<NonNegativeFloat>;
@@ -0,0 +1,55 @@
+use std::{fmt::Debug, hash::Hash};
+
+/// Width and height of a tile in pixels.
+pub const TILE_SIZE: u32 = 8;
+
+/// Marks which coordinate space a geometry value is in, so values from different spaces can't be
+/// mixed up.
+pub trait Space: Copy + Clone + Debug + PartialEq + Eq + Hash {}
+
+/// World pixels, the space screens and the camera are in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Pixels;
+
+/// World tiles, the space fillers are in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WorldTiles;
+
+/// Pixels relative to the top left of a screen, the space entities are in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ScreenPixels;
+
+/// Tiles relative to the top left of a screen, the space tile layers are in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ScreenTiles;
+
+impl Space for Pixels {}
+impl Space for WorldTiles {}
+impl Space for ScreenPixels {}
+impl Space for ScreenTiles {}
+
+/// A space measured in tiles, paired with the pixel space sharing its origin.
+pub trait TileSpace: Space {
+    type Pixels: PixelSpace<Tiles = Self>;
+}
+
+/// A space measured in pixels, paired with the tile space sharing its origin.
+pub trait PixelSpace: Space {
+    type Tiles: TileSpace<Pixels = Self>;
+}
+
+impl TileSpace for WorldTiles {
+    type Pixels = Pixels;
+}
+
+impl TileSpace for ScreenTiles {
+    type Pixels = ScreenPixels;
+}
+
+impl PixelSpace for Pixels {
+    type Tiles = WorldTiles;
+}
+
+impl PixelSpace for ScreenPixels {
+    type Tiles = ScreenTiles;
+}
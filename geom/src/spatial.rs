@@ -0,0 +1,485 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    iter::FromIterator,
+};
+
+use crate::{NonNegativeGeomUnit, Pixels, Point, PositionUnit, Rect, Space};
+
+/// Items a node holds before it splits into quadrants.
+const NODE_CAPACITY: usize = 8;
+/// Nodes this deep never split, which bounds the tree when many items share a spot.
+const MAX_DEPTH: usize = 16;
+
+/// Rects keyed by `K`, indexed for fast point, rect and nearest queries.
+///
+/// This is a quadtree where each rect is held by the smallest node that fully contains it. The
+/// tree grows to fit rects inserted outside of it, so it works for any spread of rects.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<K, T, U, S = Pixels> {
+    rects: HashMap<K, Rect<T, U, S>>,
+    root: Option<Node<K, T, U, S>>,
+}
+
+type Quadrants<K, T, U, S> = Box<[Node<K, T, U, S>; 4]>;
+
+#[derive(Debug, Clone)]
+struct Node<K, T, U, S> {
+    bounds: Rect<T, U, S>,
+    depth: usize,
+    items: Vec<(K, Rect<T, U, S>)>,
+    children: Option<Quadrants<K, T, U, S>>,
+}
+
+/// Whether `rect` lies within `bounds`, including when `rect` is empty.
+fn fits<T: PositionUnit<Size = U>, U: NonNegativeGeomUnit, S: Space>(
+    bounds: &Rect<T, U, S>,
+    rect: &Rect<T, U, S>,
+) -> bool {
+    bounds.left() <= rect.left()
+        && rect.right() <= bounds.right()
+        && bounds.top() <= rect.top()
+        && rect.bottom() <= bounds.bottom()
+}
+
+/// Squared distance from `point` to the nearest point of `rect`, zero if it's inside.
+fn distance_squared<T: PositionUnit<Size = U>, U: NonNegativeGeomUnit + Into<f64>, S: Space>(
+    rect: &Rect<T, U, S>,
+    point: Point<T, S>,
+) -> f64 {
+    let axis = |x: T, low: T, high: T| -> f64 {
+        if x < low {
+            x.distance_to(low).into()
+        } else {
+            high.distance_to(x).into()
+        }
+    };
+    let dx = axis(point.x(), rect.left(), rect.right());
+    let dy = axis(point.y(), rect.top(), rect.bottom());
+    dx * dx + dy * dy
+}
+
+impl<K: Copy + Eq + Hash, T: PositionUnit<Size = U>, U: NonNegativeGeomUnit, S: Space>
+    Node<K, T, U, S>
+{
+    fn new(bounds: Rect<T, U, S>, depth: usize) -> Self {
+        Node {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn quadrants(&self) -> Option<[Rect<T, U, S>; 4]> {
+        let b = &self.bounds;
+        let center = b.center();
+        // Integer bounds this small can't be split any further.
+        if !(b.left() < center.x() && b.top() < center.y()) {
+            return None;
+        }
+        Some([
+            Rect::from_edges(b.left(), b.top(), center.x(), center.y()),
+            Rect::from_edges(center.x(), b.top(), b.right(), center.y()),
+            Rect::from_edges(b.left(), center.y(), center.x(), b.bottom()),
+            Rect::from_edges(center.x(), center.y(), b.right(), b.bottom()),
+        ])
+    }
+
+    fn child_for(&mut self, rect: &Rect<T, U, S>) -> Option<&mut Node<K, T, U, S>> {
+        self.children
+            .as_deref_mut()?
+            .iter_mut()
+            .find(|x| fits(&x.bounds, rect))
+    }
+
+    fn insert(&mut self, key: K, rect: Rect<T, U, S>) {
+        if let Some(child) = self.child_for(&rect) {
+            return child.insert(key, rect);
+        }
+        self.items.push((key, rect));
+
+        if self.children.is_none() && self.items.len() > NODE_CAPACITY && self.depth < MAX_DEPTH {
+            if let Some(q) = self.quadrants() {
+                let depth = self.depth + 1;
+                self.children = Some(Box::new([
+                    Node::new(q[0], depth),
+                    Node::new(q[1], depth),
+                    Node::new(q[2], depth),
+                    Node::new(q[3], depth),
+                ]));
+                for (key, rect) in std::mem::take(&mut self.items) {
+                    self.insert(key, rect);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, key: K, rect: &Rect<T, U, S>) {
+        if let Some(child) = self.child_for(rect) {
+            return child.remove(key, rect);
+        }
+        if let Some(i) = self.items.iter().position(|(x, _)| *x == key) {
+            self.items.swap_remove(i);
+        }
+    }
+
+    fn children(&self) -> &[Node<K, T, U, S>] {
+        self.children.as_deref().map_or(&[], |x| &x[..])
+    }
+
+    fn query_point(&self, point: Point<T, S>, found: &mut Vec<K>) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, rect)| rect.contains(point))
+                .map(|(key, _)| *key),
+        );
+        for child in self.children() {
+            if child.bounds.contains(point) {
+                child.query_point(point, found);
+            }
+        }
+    }
+
+    fn query_rect(&self, area: &Rect<T, U, S>, found: &mut Vec<K>) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, rect)| rect.intersects(area))
+                .map(|(key, _)| *key),
+        );
+        for child in self.children() {
+            if child.bounds.intersects(area) {
+                child.query_rect(area, found);
+            }
+        }
+    }
+}
+
+/// A candidate for the nearest search, ordered by distance.
+struct Candidate<'a, K, T, U, S> {
+    distance: f64,
+    kind: CandidateKind<'a, K, T, U, S>,
+}
+
+enum CandidateKind<'a, K, T, U, S> {
+    Node(&'a Node<K, T, U, S>),
+    Item(K),
+}
+
+impl<K, T, U, S> PartialEq for Candidate<'_, K, T, U, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K, T, U, S> Eq for Candidate<'_, K, T, U, S> {}
+
+impl<K, T, U, S> PartialOrd for Candidate<'_, K, T, U, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K, T, U, S> Ord for Candidate<'_, K, T, U, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Distances are never NaN, so they're always comparable.
+        self.distance
+            .partial_cmp(&other.distance)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<K, T, U, S> Default for SpatialIndex<K, T, U, S> {
+    fn default() -> Self {
+        SpatialIndex {
+            rects: HashMap::new(),
+            root: None,
+        }
+    }
+}
+
+impl<K: Copy + Eq + Hash, T: PositionUnit<Size = U>, U: NonNegativeGeomUnit, S: Space>
+    SpatialIndex<K, T, U, S>
+{
+    pub fn new() -> Self {
+        SpatialIndex::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn get(&self, key: K) -> Option<Rect<T, U, S>> {
+        self.rects.get(&key).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, Rect<T, U, S>)> + '_ {
+        self.rects.iter().map(|(&key, &rect)| (key, rect))
+    }
+
+    /// Inserts `rect` under `key`, returning the rect it replaces.
+    pub fn insert(&mut self, key: K, rect: Rect<T, U, S>) -> Option<Rect<T, U, S>> {
+        let old = self.remove(key);
+        self.rects.insert(key, rect);
+
+        match &mut self.root {
+            Some(root) if fits(&root.bounds, &rect) => root.insert(key, rect),
+            root => {
+                // Rebuild with room to spare, so a map growing in one direction only rebuilds
+                // a logarithmic number of times.
+                let bounds = root.as_ref().map_or(rect, |x| x.bounds.union(&rect));
+                let bounds = bounds.inflate(
+                    bounds.right() - bounds.left(),
+                    bounds.bottom() - bounds.top(),
+                );
+                let mut new_root = Node::new(bounds, 0);
+                for (&key, &rect) in self.rects.iter() {
+                    new_root.insert(key, rect);
+                }
+                *root = Some(new_root);
+            }
+        }
+        old
+    }
+
+    /// Moves the rect under `key`, returning the old rect. Equivalent to [`Self::insert`].
+    pub fn update(&mut self, key: K, rect: Rect<T, U, S>) -> Option<Rect<T, U, S>> {
+        self.insert(key, rect)
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Rect<T, U, S>> {
+        let rect = self.rects.remove(&key)?;
+        if let Some(root) = &mut self.root {
+            root.remove(key, &rect);
+        }
+        Some(rect)
+    }
+
+    pub fn clear(&mut self) {
+        *self = SpatialIndex::default();
+    }
+
+    /// Keys of the rects containing `point`, in no particular order.
+    pub fn query_point(&self, point: Point<T, S>) -> Vec<K> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            root.query_point(point, &mut found);
+        }
+        found
+    }
+
+    /// Keys of the rects sharing any area with `area`, in no particular order.
+    pub fn query_rect(&self, area: &Rect<T, U, S>) -> Vec<K> {
+        let mut found = Vec::new();
+        if let Some(root) = &self.root {
+            root.query_rect(area, &mut found);
+        }
+        found
+    }
+
+    /// The key of the rect closest to `point` and the distance to it, which is zero for rects
+    /// containing `point`. Ties are broken arbitrarily.
+    pub fn nearest(&self, point: Point<T, S>) -> Option<(K, f64)>
+    where
+        U: Into<f64>,
+    {
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse(Candidate {
+            distance: 0.0,
+            kind: CandidateKind::Node(self.root.as_ref()?),
+        }));
+
+        while let Some(Reverse(candidate)) = queue.pop() {
+            match candidate.kind {
+                CandidateKind::Item(key) => return Some((key, candidate.distance.sqrt())),
+                CandidateKind::Node(node) => {
+                    for (key, rect) in node.items.iter() {
+                        queue.push(Reverse(Candidate {
+                            distance: distance_squared(rect, point),
+                            kind: CandidateKind::Item(*key),
+                        }));
+                    }
+                    for child in node.children() {
+                        queue.push(Reverse(Candidate {
+                            distance: distance_squared(&child.bounds, point),
+                            kind: CandidateKind::Node(child),
+                        }));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<K: Copy + Eq + Hash, T: PositionUnit<Size = U>, U: NonNegativeGeomUnit, S: Space>
+    FromIterator<(K, Rect<T, U, S>)> for SpatialIndex<K, T, U, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, Rect<T, U, S>)>>(iter: I) -> Self {
+        let mut index = SpatialIndex::new();
+        for (key, rect) in iter {
+            index.insert(key, rect);
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntRect;
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> IntRect {
+        Rect::from_edges(left, top, right, bottom)
+    }
+
+    fn sorted(mut keys: Vec<usize>) -> Vec<usize> {
+        keys.sort_unstable();
+        keys
+    }
+
+    /// A grid of 10 by 10 tiles with gaps, enough to split the root several times.
+    fn grid() -> SpatialIndex<usize, i32, u32> {
+        (0..400)
+            .map(|i| {
+                let (x, y) = ((i % 20) as i32 * 20, (i / 20) as i32 * 20);
+                (i, rect(x, y, x + 10, y + 10))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut index = SpatialIndex::new();
+        assert!(index.is_empty());
+        assert_eq!(index.insert(1, rect(0, 0, 10, 10)), None);
+        assert_eq!(index.insert(2, rect(20, 0, 30, 10)), None);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get(1), Some(rect(0, 0, 10, 10)));
+        assert_eq!(index.get(3), None);
+        assert_eq!(
+            index.insert(1, rect(5, 5, 15, 15)),
+            Some(rect(0, 0, 10, 10))
+        );
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.query_point(Point::new(1, 1)), Vec::<i32>::new());
+        assert_eq!(index.query_point(Point::new(12, 12)), vec![1]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut index = grid();
+        assert_eq!(index.remove(21), Some(rect(20, 20, 30, 30)));
+        assert_eq!(index.remove(21), None);
+        assert_eq!(index.len(), 399);
+        assert!(index.query_point(Point::new(25, 25)).is_empty());
+        assert_eq!(index.query_point(Point::new(5, 5)), vec![0]);
+
+        index.clear();
+        assert!(index.is_empty());
+        assert!(index.query_point(Point::new(5, 5)).is_empty());
+    }
+
+    #[test]
+    fn update_moves_rect() {
+        let mut index = grid();
+        assert_eq!(
+            index.update(0, rect(385, 385, 395, 395)),
+            Some(rect(0, 0, 10, 10))
+        );
+        assert!(index.query_point(Point::new(5, 5)).is_empty());
+        assert_eq!(
+            sorted(index.query_point(Point::new(387, 387))),
+            vec![0, 399]
+        );
+    }
+
+    #[test]
+    fn query_point_is_half_open() {
+        let index = grid();
+        assert_eq!(index.query_point(Point::new(20, 40)), vec![41]);
+        assert_eq!(index.query_point(Point::new(29, 49)), vec![41]);
+        assert!(index.query_point(Point::new(30, 40)).is_empty());
+        assert!(index.query_point(Point::new(15, 15)).is_empty());
+        assert!(index.query_point(Point::new(-5, 5)).is_empty());
+    }
+
+    #[test]
+    fn query_point_finds_overlapping_rects() {
+        let mut index = grid();
+        index.insert(1000, rect(0, 0, 400, 400));
+        index.insert(1001, rect(15, 15, 35, 35));
+        assert_eq!(
+            sorted(index.query_point(Point::new(25, 25))),
+            vec![21, 1000, 1001]
+        );
+    }
+
+    #[test]
+    fn query_rect_matches_brute_force() {
+        let index = grid();
+        for &area in [
+            rect(0, 0, 1, 1),
+            rect(10, 10, 20, 20),
+            rect(5, 5, 45, 25),
+            rect(-100, -100, 1000, 1000),
+            rect(-100, -100, 0, 0),
+        ]
+        .iter()
+        {
+            let expected: Vec<usize> = sorted(
+                index
+                    .iter()
+                    .filter(|(_, x)| x.intersects(&area))
+                    .map(|(key, _)| key)
+                    .collect(),
+            );
+            assert_eq!(sorted(index.query_rect(&area)), expected, "{:?}", area);
+        }
+        assert_eq!(index.query_rect(&rect(-100, -100, 1000, 1000)).len(), 400);
+    }
+
+    #[test]
+    fn nearest() {
+        let index = grid();
+        assert_eq!(index.nearest(Point::new(5, 5)), Some((0, 0.0)));
+        assert_eq!(index.nearest(Point::new(13, 5)), Some((0, 3.0)));
+        assert_eq!(index.nearest(Point::new(17, 5)), Some((1, 3.0)));
+        assert_eq!(index.nearest(Point::new(-3, -4)), Some((0, 5.0)));
+        assert_eq!(index.nearest(Point::new(500, 385)), Some((399, 110.0)));
+        assert_eq!(
+            SpatialIndex::<usize, i32, u32>::new().nearest(Point::new(0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn grows_to_fit_distant_rects() {
+        let mut index = SpatialIndex::new();
+        index.insert(0, rect(0, 0, 10, 10));
+        for i in 1..50 {
+            let offset = 1 << (i % 20);
+            let offset = if i % 2 == 0 { offset } else { -offset };
+            index.insert(i, rect(offset, -offset, offset + 5, -offset + 5));
+        }
+        assert_eq!(index.len(), 50);
+        for (key, rect) in index.iter().collect::<Vec<_>>() {
+            assert!(index.query_point(rect.top_left()).contains(&key));
+            assert_eq!(index.nearest(rect.top_left()).map(|x| x.1), Some(0.0));
+        }
+        assert_eq!(
+            index
+                .query_rect(&rect(-2_000_000, -2_000_000, 2_000_000, 2_000_000))
+                .len(),
+            50
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Generation of fillers from the screen layout.
+//!
+//! Fillers are solid blocks covering the space between screens. Generated fillers cover the
+//! bounding box of every screen, less the screens themselves, using few rects.
+
+use fujiformer_geom::{Point, Rect, WorldTiles};
+
+use crate::{CelesteMap, Filler};
+
+#[derive(Debug, Clone, Default)]
+pub struct AutofillOptions {
+    /// Tiles the covered region extends past the screens on each side.
+    pub margin: u32,
+    /// Tiles around each screen left without filler.
+    pub screen_padding: u32,
+}
+
+/// Splits the cells where `free[row][column]` into rects, extending each rect right and then
+/// down as far as possible. Rects are given as `(left, top, right, bottom)` in cells.
+fn greedy_rects(free: &[Vec<bool>]) -> Vec<(usize, usize, usize, usize)> {
+    let mut used: Vec<Vec<bool>> = free.iter().map(|x| vec![false; x.len()]).collect();
+    let available =
+        |used: &[Vec<bool>], row: usize, column: usize| free[row][column] && !used[row][column];
+
+    let mut rects = Vec::new();
+    for top in 0..free.len() {
+        for left in 0..free[top].len() {
+            if !available(&used, top, left) {
+                continue;
+            }
+            let mut right = left + 1;
+            while right < free[top].len() && available(&used, top, right) {
+                right += 1;
+            }
+            let mut bottom = top + 1;
+            while bottom < free.len() && (left..right).all(|x| available(&used, bottom, x)) {
+                bottom += 1;
+            }
+            for row in used[top..bottom].iter_mut() {
+                row[left..right].iter_mut().for_each(|x| *x = true);
+            }
+            rects.push((left, top, right, bottom));
+        }
+    }
+    rects
+}
+
+/// Computes fillers for `map`'s screens, ignoring its current fillers.
+///
+/// Screens are rounded out to whole tiles so fillers never overlap them. The region is split at
+/// every screen edge and filled greedily both row first and column first, keeping whichever
+/// gives fewer rects, which is close to the fewest possible for typical layouts.
+pub fn generate_fillers(map: &CelesteMap, options: &AutofillOptions) -> Vec<Filler> {
+    let screens: Vec<Rect<i32, u32, WorldTiles>> = map
+        .screens()
+        .iter()
+        .map(|x| x.shape().to_covering_tiles())
+        .filter(|x| !x.is_empty())
+        .collect();
+    let region = match Rect::bounding_box(screens.iter().copied()) {
+        Some(x) => x.inflate(options.margin as i32, options.margin as i32),
+        None => return Vec::new(),
+    };
+    let padding = options.screen_padding as i32;
+    let holes: Vec<_> = screens
+        .iter()
+        .map(|x| x.inflate(padding, padding))
+        .collect();
+
+    let edges = |edges: &mut dyn Iterator<Item = i32>, low: i32, high: i32| {
+        let mut edges: Vec<i32> = edges
+            .map(|x| x.clamp(low, high))
+            .chain(vec![low, high])
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    };
+    let xs = edges(
+        &mut holes.iter().flat_map(|x| vec![x.left(), x.right()]),
+        region.left(),
+        region.right(),
+    );
+    let ys = edges(
+        &mut holes.iter().flat_map(|x| vec![x.top(), x.bottom()]),
+        region.top(),
+        region.bottom(),
+    );
+
+    // Every hole edge is a cell edge, so a cell is in a hole exactly when its top left is.
+    let free: Vec<Vec<bool>> = ys[..ys.len() - 1]
+        .iter()
+        .map(|&y| {
+            xs[..xs.len() - 1]
+                .iter()
+                .map(|&x| !holes.iter().any(|hole| hole.contains(Point::new(x, y))))
+                .collect()
+        })
+        .collect();
+    let transposed: Vec<Vec<bool>> = (0..xs.len() - 1)
+        .map(|column| free.iter().map(|row| row[column]).collect())
+        .collect();
+
+    let rows_first = greedy_rects(&free);
+    let columns_first: Vec<_> = greedy_rects(&transposed)
+        .into_iter()
+        .map(|(top, left, bottom, right)| (left, top, right, bottom))
+        .collect();
+    let rects = if columns_first.len() < rows_first.len() {
+        columns_first
+    } else {
+        rows_first
+    };
+
+    rects
+        .into_iter()
+        .map(|(left, top, right, bottom)| {
+            Filler::new(Rect::from_edges(xs[left], ys[top], xs[right], ys[bottom]))
+        })
+        .collect()
+}
+
+/// Replaces `map`'s fillers with generated ones, returning the old fillers.
+pub fn replace_fillers(map: &mut CelesteMap, options: &AutofillOptions) -> Vec<Filler> {
+    let fillers = generate_fillers(map, options);
+    std::mem::replace(map.fillers_mut(), fillers)
+}
+
+#[cfg(test)]
+mod tests {
+    use fujiformer_geom::IntRect;
+
+    use super::*;
+    use crate::Screen;
+
+    fn map(screens: &[IntRect]) -> CelesteMap {
+        let mut map = CelesteMap::new("test".to_string());
+        for (i, &rect) in screens.iter().enumerate() {
+            map.screens_mut()
+                .push(Screen::new(format!("lvl_{}", i), rect));
+        }
+        map
+    }
+
+    /// Checks every tile of the region is covered by exactly one filler when it is outside the
+    /// padded screens, and by none when it is inside.
+    fn assert_covers(screens: &[IntRect], options: &AutofillOptions) -> Vec<Filler> {
+        let fillers = generate_fillers(&map(screens), options);
+        let holes: Vec<_> = screens
+            .iter()
+            .map(|x| {
+                let padding = options.screen_padding as i32;
+                x.to_covering_tiles().inflate(padding, padding)
+            })
+            .collect();
+        let margin = options.margin as i32;
+        let region = Rect::bounding_box(screens.iter().map(|x| x.to_covering_tiles()))
+            .unwrap()
+            .inflate(margin, margin);
+
+        for y in region.top() - 2..region.bottom() + 2 {
+            for x in region.left() - 2..region.right() + 2 {
+                let point = Point::new(x, y);
+                let covering = fillers.iter().filter(|f| f.shape().contains(point)).count();
+                let expected = region.contains(point) && !holes.iter().any(|h| h.contains(point));
+                assert_eq!(covering, expected as usize, "tile {:?}", point);
+            }
+        }
+        fillers
+    }
+
+    #[test]
+    fn no_screens_gives_no_fillers() {
+        assert!(generate_fillers(&map(&[]), &AutofillOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn fills_between_screens() {
+        let screens = [
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(400, 80, 720, 264),
+            Rect::from_edges(-160, 300, 160, 484),
+        ];
+        assert_covers(&screens, &AutofillOptions::default());
+    }
+
+    #[test]
+    fn avoids_padding_and_fills_margin() {
+        let screens = [
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(320, 240, 640, 424),
+        ];
+        let options = AutofillOptions {
+            margin: 3,
+            screen_padding: 2,
+        };
+        assert_covers(&screens, &options);
+    }
+
+    #[test]
+    fn rounds_screens_out_to_whole_tiles() {
+        let screens = [
+            Rect::from_edges(3, 5, 317, 181),
+            Rect::from_edges(403, 85, 717, 263),
+        ];
+        assert_covers(&screens, &AutofillOptions::default());
+    }
+
+    #[test]
+    fn adjacent_screens_leave_no_filler() {
+        let screens = [
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(320, 0, 640, 184),
+        ];
+        assert!(assert_covers(&screens, &AutofillOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+use std::{fs::File, io::BufReader, process::exit};
+
+use fujiformer_io::{
+    lint::{lint, Severity},
+    CelesteMap,
+};
+
+fn main() {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: fujiformer-lint <map.bin>...");
+        exit(2);
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        let map = match File::open(path)
+            .map_err(Into::into)
+            .and_then(|file| CelesteMap::read(BufReader::new(file)))
+        {
+            Ok(map) => map,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        for diagnostic in lint(&map).iter() {
+            println!("{}: {}", path, diagnostic);
+            failed |= diagnostic.severity() == Severity::Error;
+        }
+    }
+
+    if failed {
+        exit(1);
+    }
+}
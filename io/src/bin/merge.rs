@@ -0,0 +1,55 @@
+//! A git merge driver for map files, configured with
+//!
+//! ```text
+//! [merge "celeste-map"]
+//!     name = Celeste map merge
+//!     driver = fujiformer-merge %O %A %B
+//! ```
+//!
+//! and a `*.bin merge=celeste-map` line in `.gitattributes`.
+
+use std::{fs::File, io::BufReader, process::exit};
+
+use fujiformer_io::{merge::merge, CelesteMap};
+
+fn read_map(path: &str) -> CelesteMap {
+    match File::open(path)
+        .map_err(Into::into)
+        .and_then(|file| CelesteMap::read(BufReader::new(file)))
+    {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            exit(2);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (base, ours, theirs) = match args.as_slice() {
+        [base, ours, theirs] => (base, ours, theirs),
+        _ => {
+            eprintln!("usage: fujiformer-merge <base> <ours> <theirs>");
+            exit(2);
+        }
+    };
+
+    let result = merge(&read_map(base), &read_map(ours), &read_map(theirs));
+    for conflict in result.conflicts().iter() {
+        eprintln!("conflict: {}", conflict);
+    }
+
+    // Git expects the result in place of `ours`.
+    if let Err(e) = File::create(ours)
+        .map_err(Into::into)
+        .and_then(|file| result.map().write(file))
+    {
+        eprintln!("{}: {}", ours, e);
+        exit(2);
+    }
+
+    if !result.is_clean() {
+        exit(1);
+    }
+}
@@ -0,0 +1,462 @@
+//! Semantic comparison of two [`CelesteMap`]s.
+
+use std::{collections::HashMap, fmt::Display};
+
+use fujiformer_geom::{IntPoint, IntSize, Point, ScreenPixels, ScreenTiles};
+
+use crate::{internal::Value, CelesteMap, Entity, Screen, Tiles};
+
+/// Which list of a screen an entity belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    Entity,
+    Trigger,
+}
+
+impl Display for EntityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntityKind::Entity => write!(f, "entity"),
+            EntityKind::Trigger => write!(f, "trigger"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileLayer {
+    Solids,
+    Bg,
+}
+
+impl TileLayer {
+    pub fn get(self, screen: &Screen) -> &Tiles {
+        match self {
+            TileLayer::Solids => screen.solids(),
+            TileLayer::Bg => screen.bg(),
+        }
+    }
+
+    pub fn get_mut(self, screen: &mut Screen) -> &mut Tiles {
+        match self {
+            TileLayer::Solids => screen.solids_mut(),
+            TileLayer::Bg => screen.bg_mut(),
+        }
+    }
+}
+
+impl Display for TileLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileLayer::Solids => write!(f, "solids"),
+            TileLayer::Bg => write!(f, "bg"),
+        }
+    }
+}
+
+/// A property that differs, where `None` means the property is absent on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyChange {
+    pub key: String,
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+impl Display for PropertyChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) => write!(f, "{}: {} -> {}", self.key, from, to),
+            (None, Some(to)) => write!(f, "{}: set to {}", self.key, to),
+            (Some(from), None) => write!(f, "{}: unset (was {})", self.key, from),
+            (None, None) => write!(f, "{}: unchanged", self.key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityEdit {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Moved {
+        from: Point<i32, ScreenPixels>,
+        to: Point<i32, ScreenPixels>,
+    },
+    NodesChanged {
+        from: Vec<Point<i32, ScreenPixels>>,
+        to: Vec<Point<i32, ScreenPixels>>,
+    },
+    Property(PropertyChange),
+}
+
+impl Display for EntityEdit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntityEdit::Renamed { from, to } => write!(f, "type {} -> {}", from, to),
+            EntityEdit::Moved { from, to } => write!(f, "moved {:?} -> {:?}", from, to),
+            EntityEdit::NodesChanged { from, to } => write!(f, "nodes {:?} -> {:?}", from, to),
+            EntityEdit::Property(change) => write!(f, "{}", change),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    ScreenAdded {
+        screen: String,
+    },
+    ScreenRemoved {
+        screen: String,
+    },
+    ScreenMoved {
+        screen: String,
+        from: IntPoint,
+        to: IntPoint,
+    },
+    ScreenResized {
+        screen: String,
+        from: IntSize,
+        to: IntSize,
+    },
+    /// Cells, in tiles relative to the screen, whose tile differs.
+    TilesChanged {
+        screen: String,
+        layer: TileLayer,
+        cells: Vec<Point<i32, ScreenTiles>>,
+    },
+    EntityAdded {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        name: String,
+    },
+    EntityRemoved {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        name: String,
+    },
+    EntityEdited {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        edits: Vec<EntityEdit>,
+    },
+    /// A screen setting changed, or a map setting when `screen` is `None`.
+    SettingChanged {
+        screen: Option<String>,
+        change: PropertyChange,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::ScreenAdded { screen } => write!(f, "+ screen {}", screen),
+            Change::ScreenRemoved { screen } => write!(f, "- screen {}", screen),
+            Change::ScreenMoved { screen, from, to } => {
+                write!(f, "~ screen {}: moved {:?} -> {:?}", screen, from, to)
+            }
+            Change::ScreenResized { screen, from, to } => {
+                write!(f, "~ screen {}: resized {:?} -> {:?}", screen, from, to)
+            }
+            Change::TilesChanged {
+                screen,
+                layer,
+                cells,
+            } => {
+                write!(f, "~ screen {}: {} {} changed", screen, cells.len(), layer)?;
+                let min_x = cells.iter().map(|x| x.x()).min();
+                let min_y = cells.iter().map(|x| x.y()).min();
+                let max_x = cells.iter().map(|x| x.x()).max();
+                let max_y = cells.iter().map(|x| x.y()).max();
+                if let (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) =
+                    (min_x, min_y, max_x, max_y)
+                {
+                    write!(
+                        f,
+                        " within {:?}..={:?}",
+                        Point::<_, ScreenTiles>::new(min_x, min_y),
+                        Point::<_, ScreenTiles>::new(max_x, max_y)
+                    )?;
+                }
+                Ok(())
+            }
+            Change::EntityAdded {
+                screen,
+                kind,
+                id,
+                name,
+            } => write!(f, "+ screen {}: {} {} ({})", screen, kind, id, name),
+            Change::EntityRemoved {
+                screen,
+                kind,
+                id,
+                name,
+            } => write!(f, "- screen {}: {} {} ({})", screen, kind, id, name),
+            Change::EntityEdited {
+                screen,
+                kind,
+                id,
+                edits,
+            } => {
+                write!(f, "~ screen {}: {} {}:", screen, kind, id)?;
+                for (i, edit) in edits.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, edit)?;
+                }
+                Ok(())
+            }
+            Change::SettingChanged {
+                screen: Some(screen),
+                change,
+            } => write!(f, "~ screen {}: {}", screen, change),
+            Change::SettingChanged {
+                screen: None,
+                change,
+            } => write!(f, "~ map: {}", change),
+        }
+    }
+}
+
+/// Everything that changed between two maps.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapDiff {
+    changes: Vec<Change>,
+}
+
+impl MapDiff {
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Renders one change per line.
+impl Display for MapDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares `old` against `new`, matching screens by name and entities by id.
+pub fn diff(old: &CelesteMap, new: &CelesteMap) -> MapDiff {
+    let mut changes = Vec::new();
+
+    for change in diff_properties(map_settings(old), map_settings(new)) {
+        changes.push(Change::SettingChanged {
+            screen: None,
+            change,
+        });
+    }
+
+    let new_screens: HashMap<&str, &Screen> = new.screens().iter().map(|x| (x.name(), x)).collect();
+    let old_screens: HashMap<&str, &Screen> = old.screens().iter().map(|x| (x.name(), x)).collect();
+
+    for old_screen in old.screens().iter() {
+        match new_screens.get(old_screen.name()) {
+            Some(new_screen) => diff_screens(old_screen, new_screen, &mut changes),
+            None => changes.push(Change::ScreenRemoved {
+                screen: old_screen.name().to_string(),
+            }),
+        }
+    }
+
+    for new_screen in new.screens().iter() {
+        if !old_screens.contains_key(new_screen.name()) {
+            changes.push(Change::ScreenAdded {
+                screen: new_screen.name().to_string(),
+            });
+        }
+    }
+
+    MapDiff { changes }
+}
+
+/// Map wide settings live in the `meta` node, if the map has one.
+fn map_settings(map: &CelesteMap) -> &[(String, Value)] {
+    map.unread
+        .children()
+        .iter()
+        .find(|x| x.name() == "meta")
+        .map_or(&[], |x| x.properties())
+}
+
+/// Compares property lists regardless of their order.
+pub(crate) fn diff_properties(
+    old: &[(String, Value)],
+    new: &[(String, Value)],
+) -> Vec<PropertyChange> {
+    let new_map: HashMap<&str, &Value> = new.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let old_map: HashMap<&str, &Value> = old.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    let mut changes = Vec::new();
+
+    for (key, old_value) in old.iter() {
+        match new_map.get(key.as_str()) {
+            Some(new_value) if *new_value == old_value => {}
+            new_value => changes.push(PropertyChange {
+                key: key.clone(),
+                from: Some(old_value.clone()),
+                to: new_value.map(|x| (*x).clone()),
+            }),
+        }
+    }
+
+    for (key, new_value) in new.iter() {
+        if !old_map.contains_key(key.as_str()) {
+            changes.push(PropertyChange {
+                key: key.clone(),
+                from: None,
+                to: Some(new_value.clone()),
+            });
+        }
+    }
+
+    changes
+}
+
+fn diff_screens(old: &Screen, new: &Screen, changes: &mut Vec<Change>) {
+    let screen = || new.name().to_string();
+    let (old_shape, new_shape) = (old.shape(), new.shape());
+
+    if old_shape.position() != new_shape.position() {
+        changes.push(Change::ScreenMoved {
+            screen: screen(),
+            from: old_shape.position(),
+            to: new_shape.position(),
+        });
+    }
+
+    if old_shape.size() != new_shape.size() {
+        changes.push(Change::ScreenResized {
+            screen: screen(),
+            from: old_shape.size(),
+            to: new_shape.size(),
+        });
+    }
+
+    for change in diff_properties(old.properties(), new.properties()) {
+        changes.push(Change::SettingChanged {
+            screen: Some(screen()),
+            change,
+        });
+    }
+
+    for &layer in [TileLayer::Solids, TileLayer::Bg].iter() {
+        let cells = diff_tiles(layer.get(old), layer.get(new));
+        if !cells.is_empty() {
+            changes.push(Change::TilesChanged {
+                screen: screen(),
+                layer,
+                cells,
+            });
+        }
+    }
+
+    diff_entities(
+        new.name(),
+        EntityKind::Entity,
+        old.entities(),
+        new.entities(),
+        changes,
+    );
+    diff_entities(
+        new.name(),
+        EntityKind::Trigger,
+        old.triggers(),
+        new.triggers(),
+        changes,
+    );
+}
+
+/// Cells that differ, treating cells outside a grid as missing.
+pub(crate) fn diff_tiles(old: &Tiles, new: &Tiles) -> Vec<Point<i32, ScreenTiles>> {
+    let width = old.width().max(new.width());
+    let height = old.height().max(new.height());
+    let mut cells = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if old.get(x, y) != new.get(x, y) {
+                cells.push(Point::new(x as i32, y as i32));
+            }
+        }
+    }
+    cells
+}
+
+fn diff_entities(
+    screen: &str,
+    kind: EntityKind,
+    old: &[Entity],
+    new: &[Entity],
+    changes: &mut Vec<Change>,
+) {
+    let new_entities: HashMap<i32, &Entity> = new.iter().map(|x| (x.id(), x)).collect();
+    let old_entities: HashMap<i32, &Entity> = old.iter().map(|x| (x.id(), x)).collect();
+
+    for old_entity in old.iter() {
+        match new_entities.get(&old_entity.id()) {
+            Some(new_entity) => {
+                let edits = diff_entity(old_entity, new_entity);
+                if !edits.is_empty() {
+                    changes.push(Change::EntityEdited {
+                        screen: screen.to_string(),
+                        kind,
+                        id: old_entity.id(),
+                        edits,
+                    });
+                }
+            }
+            None => changes.push(Change::EntityRemoved {
+                screen: screen.to_string(),
+                kind,
+                id: old_entity.id(),
+                name: old_entity.name().to_string(),
+            }),
+        }
+    }
+
+    for new_entity in new.iter() {
+        if !old_entities.contains_key(&new_entity.id()) {
+            changes.push(Change::EntityAdded {
+                screen: screen.to_string(),
+                kind,
+                id: new_entity.id(),
+                name: new_entity.name().to_string(),
+            });
+        }
+    }
+}
+
+pub(crate) fn diff_entity(old: &Entity, new: &Entity) -> Vec<EntityEdit> {
+    let mut edits = Vec::new();
+    if old.name() != new.name() {
+        edits.push(EntityEdit::Renamed {
+            from: old.name().to_string(),
+            to: new.name().to_string(),
+        });
+    }
+    if old.position() != new.position() {
+        edits.push(EntityEdit::Moved {
+            from: old.position(),
+            to: new.position(),
+        });
+    }
+    if old.nodes() != new.nodes() {
+        edits.push(EntityEdit::NodesChanged {
+            from: old.nodes().to_vec(),
+            to: new.nodes().to_vec(),
+        });
+    }
+    edits.extend(
+        diff_properties(old.properties(), new.properties())
+            .into_iter()
+            .map(EntityEdit::Property),
+    );
+    edits
+}
@@ -0,0 +1,216 @@
+use std::convert::TryFrom;
+
+use fujiformer_geom::{Point, Polyline, ScreenPixels};
+use thiserror::Error;
+
+use crate::internal::{Node, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    name: String,
+    id: i32,
+    position: Point<i32, ScreenPixels>,
+    nodes: Vec<Point<i32, ScreenPixels>>,
+    /// Everything but the position of each node, by index.
+    pub(crate) unread_nodes: Vec<Node>,
+    pub(crate) unread: Node,
+}
+
+impl Entity {
+    pub fn new(name: String, id: i32, position: Point<i32, ScreenPixels>) -> Self {
+        Entity {
+            unread: Node::new(name.clone()),
+            name,
+            id,
+            position,
+            nodes: Vec::new(),
+            unread_nodes: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn id_mut(&mut self) -> &mut i32 {
+        &mut self.id
+    }
+
+    /// Position relative to the top left corner of the containing screen.
+    pub fn position(&self) -> Point<i32, ScreenPixels> {
+        self.position
+    }
+
+    pub fn position_mut(&mut self) -> &mut Point<i32, ScreenPixels> {
+        &mut self.position
+    }
+
+    /// Points the entity moves between or along, in order and relative to the top left corner of
+    /// the containing screen.
+    pub fn nodes(&self) -> &[Point<i32, ScreenPixels>] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut Vec<Point<i32, ScreenPixels>> {
+        &mut self.nodes
+    }
+
+    /// Properties of the node at `index` other than its position, which some modded entities
+    /// use. Nodes added since loading have none.
+    pub fn node_properties(&self, index: usize) -> &[(String, Value)] {
+        self.unread_nodes.get(index).map_or(&[], |x| x.properties())
+    }
+
+    pub fn node_properties_mut(&mut self, index: usize) -> &mut Vec<(String, Value)> {
+        while self.unread_nodes.len() <= index {
+            self.unread_nodes.push(Node::new("node".into()));
+        }
+        self.unread_nodes[index].properties_mut()
+    }
+
+    /// The path from the entity's position through each of its nodes.
+    pub fn path(&self) -> Polyline<ScreenPixels> {
+        std::iter::once(self.position)
+            .chain(self.nodes.iter().copied())
+            .collect()
+    }
+
+    /// Properties other than the id and position.
+    pub fn properties(&self) -> &[(String, Value)] {
+        self.unread.properties()
+    }
+
+    pub fn properties_mut(&mut self) -> &mut Vec<(String, Value)> {
+        self.unread.properties_mut()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EntitiesDecodeError {
+    #[error("entity missing id")]
+    MissingId,
+    #[error("entity id not int")]
+    IdNotInt,
+    #[error("entity missing x value")]
+    MissingX,
+    #[error("entity x value not int")]
+    XNotInt,
+    #[error("entity missing y value")]
+    MissingY,
+    #[error("entity y value not int")]
+    YNotInt,
+    #[error("entity node missing x value")]
+    NodeMissingX,
+    #[error("entity node x value not int")]
+    NodeXNotInt,
+    #[error("entity node missing y value")]
+    NodeMissingY,
+    #[error("entity node y value not int")]
+    NodeYNotInt,
+}
+
+/// Positions of the `node` children of an entity, along with whatever else they hold.
+type DecodedNodes = (Vec<Point<i32, ScreenPixels>>, Vec<Node>);
+
+/// Takes the `node` children of an entity, splitting their positions from anything else on them.
+fn decode_nodes(entity: &mut Node) -> Result<DecodedNodes, EntitiesDecodeError> {
+    let (node_children, others) = std::mem::take(entity.children_mut())
+        .into_iter()
+        .partition::<Vec<_>, _>(|x| x.name() == "node");
+    *entity.children_mut() = others;
+
+    let mut nodes = Vec::with_capacity(node_children.len());
+    let mut unread = Vec::with_capacity(node_children.len());
+    for mut node in node_children.into_iter() {
+        let (mut x, mut y) = (None, None);
+        node.properties_mut().retain(|(key, value)| {
+            match key.as_str() {
+                "x" => x = Some(i32::try_from(value).map_err(|_| EntitiesDecodeError::NodeXNotInt)),
+                "y" => y = Some(i32::try_from(value).map_err(|_| EntitiesDecodeError::NodeYNotInt)),
+                _ => return true,
+            };
+            false
+        });
+        nodes.push(Point::new(
+            x.ok_or(EntitiesDecodeError::NodeMissingX)??,
+            y.ok_or(EntitiesDecodeError::NodeMissingY)??,
+        ));
+        unread.push(node);
+    }
+    Ok((nodes, unread))
+}
+
+/// Decodes the children of an `entities` or `triggers` node.
+pub fn decode_entities(node: &mut Node) -> Result<Vec<Entity>, EntitiesDecodeError> {
+    let mut entities = Vec::with_capacity(node.children().len());
+
+    for mut child in std::mem::take(node.children_mut()).into_iter() {
+        let (mut id, mut x, mut y) = (None, None, None);
+        child.properties_mut().retain(|(key, value)| {
+            match key.as_str() {
+                "id" => id = Some(i32::try_from(value).map_err(|_| EntitiesDecodeError::IdNotInt)),
+                "x" => x = Some(i32::try_from(value).map_err(|_| EntitiesDecodeError::XNotInt)),
+                "y" => y = Some(i32::try_from(value).map_err(|_| EntitiesDecodeError::YNotInt)),
+                _ => return true,
+            };
+            false
+        });
+        let (id, x, y) = (
+            id.ok_or(EntitiesDecodeError::MissingId)??,
+            x.ok_or(EntitiesDecodeError::MissingX)??,
+            y.ok_or(EntitiesDecodeError::MissingY)??,
+        );
+        let (nodes, unread_nodes) = decode_nodes(&mut child)?;
+        entities.push(Entity {
+            name: child.name().to_string(),
+            id,
+            position: Point::new(x, y),
+            nodes,
+            unread_nodes,
+            unread: child,
+        });
+    }
+
+    Ok(entities)
+}
+
+/// Encodes entities as the children of a node called `name`.
+pub fn encode_entities(name: &str, entities: &[Entity]) -> Node {
+    let mut node = Node::new(name.into());
+    for entity in entities.iter() {
+        let mut child = Node::new(entity.name.clone());
+        child.push_property("id".into(), entity.id.into());
+        child.push_property("x".into(), entity.position.x().into());
+        child.push_property("y".into(), entity.position.y().into());
+        for (key, value) in entity.unread.properties().iter() {
+            child.push_property(key.clone(), value.clone());
+        }
+        for (i, position) in entity.nodes.iter().enumerate() {
+            let mut grandchild = Node::new("node".into());
+            grandchild.push_property("x".into(), position.x().into());
+            grandchild.push_property("y".into(), position.y().into());
+            if let Some(unread) = entity.unread_nodes.get(i) {
+                for (key, value) in unread.properties().iter() {
+                    grandchild.push_property(key.clone(), value.clone());
+                }
+                for great_grandchild in unread.children().iter() {
+                    grandchild.push_child(great_grandchild.clone());
+                }
+            }
+            child.push_child(grandchild);
+        }
+        for grandchild in entity.unread.children().iter() {
+            child.push_child(grandchild.clone());
+        }
+        node.push_child(child);
+    }
+    node
+}
@@ -1,28 +1,33 @@
 use std::convert::TryFrom;
 
-use fujiformer_geom::{IntRect, Point, Rect, Size};
+use fujiformer_geom::{IntRect, Point, Rect, Size, WorldTiles};
 use log::warn;
 use thiserror::Error;
 
-use crate::CelesteMap;
+use crate::{internal::Node, CelesteMap};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Filler {
-    rect: IntRect,
+    rect: Rect<i32, u32, WorldTiles>,
 }
 
 impl Filler {
-    pub fn new(rect: IntRect) -> Self {
+    pub fn new(rect: Rect<i32, u32, WorldTiles>) -> Self {
         Filler { rect }
     }
 
-    pub fn shape(&self) -> IntRect {
+    pub fn shape(&self) -> Rect<i32, u32, WorldTiles> {
         self.rect
     }
 
-    pub fn shape_mut(&mut self) -> &mut IntRect {
+    pub fn shape_mut(&mut self) -> &mut Rect<i32, u32, WorldTiles> {
         &mut self.rect
     }
+
+    /// The filler's shape in pixels, as fillers are stored in 8px tiles.
+    pub fn pixel_shape(&self) -> IntRect {
+        self.rect.to_pixels()
+    }
 }
 
 #[derive(Error, Debug)]
@@ -87,3 +92,17 @@ pub fn decode_fillers(map: &mut CelesteMap) -> Result<(), FillersDecodeError> {
 
     Ok(())
 }
+
+pub fn encode_fillers(map: &CelesteMap) -> Node {
+    let mut node = Node::new("Filler".into());
+    for filler in map.fillers().iter() {
+        let (pos, size) = (filler.rect.position(), filler.rect.size());
+        let mut child = Node::new("rect".into());
+        child.push_property("x".into(), pos.x().into());
+        child.push_property("y".into(), pos.y().into());
+        child.push_property("w".into(), (size.width() as i32).into());
+        child.push_property("h".into(), (size.height() as i32).into());
+        node.push_child(child);
+    }
+    node
+}
@@ -0,0 +1,373 @@
+//! Map wide management of entity and trigger ids.
+//!
+//! The game tracks things like collected keys and strawberries by id, so ids must be unique across
+//! the whole map. Entities that refer to other entities do so through integer properties, which
+//! are rewritten alongside the ids when given as `reference_keys`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{diff::EntityKind, internal::Value, CelesteMap, Entity, Screen};
+
+/// Old to new ids from renumbering.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdMapping {
+    ids: HashMap<i32, i32>,
+    /// Old ids that were renumbered for some users but kept by another, so a reference to one
+    /// could mean either.
+    ambiguous: HashSet<i32>,
+}
+
+impl IdMapping {
+    pub fn get(&self, old: i32) -> Option<i32> {
+        self.ids.get(&old).copied()
+    }
+
+    pub fn insert(&mut self, old: i32, new: i32) {
+        self.ids.insert(old, new);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.ids.iter().map(|(&old, &new)| (old, new))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Marks `old` as still used alongside its renumbered copies, so references to it are left
+    /// alone.
+    pub fn mark_ambiguous(&mut self, old: i32) {
+        self.ambiguous.insert(old);
+    }
+
+    /// Old ids whose references weren't rewritten, as they could mean the user that kept the id
+    /// or one that was renumbered. These need resolving by hand.
+    pub fn ambiguous(&self) -> impl Iterator<Item = i32> + '_ {
+        self.ambiguous.iter().copied()
+    }
+
+    /// Rewrites integer properties named in `reference_keys` that refer to a renumbered id,
+    /// except ambiguous ones.
+    pub fn apply_to_references(&self, entities: &mut [Entity], reference_keys: &[&str]) {
+        for entity in entities.iter_mut() {
+            for (key, value) in entity.properties_mut().iter_mut() {
+                if let Value::Int(id) = value {
+                    if reference_keys.contains(&key.as_str()) {
+                        if self.ambiguous.contains(id) {
+                            continue;
+                        }
+                        if let Some(new) = self.get(*id) {
+                            *id = new;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Hands out ids not used anywhere in a map.
+#[derive(Debug, Clone)]
+pub struct IdAllocator {
+    used: HashSet<i32>,
+    next: i32,
+}
+
+impl IdAllocator {
+    pub fn new(used: HashSet<i32>) -> Self {
+        // With i32::MAX in use the search starts over from zero, finding the lowest free id.
+        let next = used
+            .iter()
+            .max()
+            .and_then(|x| x.checked_add(1))
+            .unwrap_or(0);
+        IdAllocator { used, next }
+    }
+
+    pub fn for_map(map: &CelesteMap) -> Self {
+        IdAllocator::new(used_ids(map))
+    }
+
+    pub fn is_used(&self, id: i32) -> bool {
+        self.used.contains(&id)
+    }
+
+    /// Marks `id` as used, returning whether it was free.
+    pub fn reserve(&mut self, id: i32) -> bool {
+        self.used.insert(id)
+    }
+
+    /// Allocates an id above every id used so far, or once those run out, the lowest free id.
+    pub fn allocate(&mut self) -> i32 {
+        while self.used.contains(&self.next) {
+            self.next = match self.next.checked_add(1) {
+                Some(x) => x,
+                None => self.lowest_free(),
+            };
+        }
+        self.used.insert(self.next);
+        self.next
+    }
+
+    fn lowest_free(&self) -> i32 {
+        (0..=i32::MAX)
+            .chain(i32::MIN..0)
+            .find(|x| !self.used.contains(x))
+            .expect("every id is used")
+    }
+}
+
+fn all_entities(screen: &Screen) -> impl Iterator<Item = (EntityKind, &Entity)> {
+    screen
+        .entities()
+        .iter()
+        .map(|x| (EntityKind::Entity, x))
+        .chain(screen.triggers().iter().map(|x| (EntityKind::Trigger, x)))
+}
+
+pub fn used_ids(map: &CelesteMap) -> HashSet<i32> {
+    map.screens()
+        .iter()
+        .flat_map(all_entities)
+        .map(|(_, x)| x.id())
+        .collect()
+}
+
+/// An id used by more than one entity or trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdCollision {
+    pub id: i32,
+    /// Every user of the id, as screen name and list.
+    pub users: Vec<(String, EntityKind)>,
+}
+
+pub fn find_collisions(map: &CelesteMap) -> Vec<IdCollision> {
+    let mut users: HashMap<i32, Vec<(String, EntityKind)>> = HashMap::new();
+    let mut order = Vec::new();
+    for screen in map.screens().iter() {
+        for (kind, entity) in all_entities(screen) {
+            let entry = users.entry(entity.id()).or_default();
+            if entry.is_empty() {
+                order.push(entity.id());
+            }
+            entry.push((screen.name().to_string(), kind));
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|id| {
+            let users = users.remove(&id)?;
+            if users.len() > 1 {
+                Some(IdCollision { id, users })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Gives every entity and trigger in `screen` that collides with `allocator` a fresh id, such as
+/// before adding a screen pasted from another map.
+///
+/// References are rewritten to follow the renumbered ids, unless the old id had several users in
+/// the screen. Those ids are left for the caller in [`IdMapping::ambiguous`]. Only references
+/// within `screen` are rewritten, so references from other screens keep the old id.
+pub fn renumber_screen(
+    screen: &mut Screen,
+    allocator: &mut IdAllocator,
+    reference_keys: &[&str],
+) -> IdMapping {
+    renumber_where(screen, allocator, reference_keys, |allocator, id| {
+        !allocator.reserve(id)
+    })
+}
+
+/// Gives every entity and trigger in `screen` whose id `collides` a fresh id from `allocator`.
+pub(crate) fn renumber_where(
+    screen: &mut Screen,
+    allocator: &mut IdAllocator,
+    reference_keys: &[&str],
+    mut collides: impl FnMut(&mut IdAllocator, i32) -> bool,
+) -> IdMapping {
+    // Ids with several users in the screen, where a reference could mean any of them.
+    let mut seen = HashSet::new();
+    let shared: HashSet<i32> = all_entities(screen)
+        .map(|(_, x)| x.id())
+        .filter(|&id| !seen.insert(id))
+        .collect();
+
+    let mut mapping = IdMapping::default();
+    let mut renumber = |entities: &mut [Entity]| {
+        for entity in entities.iter_mut() {
+            if collides(allocator, entity.id()) {
+                let new = allocator.allocate();
+                mapping.insert(entity.id(), new);
+                *entity.id_mut() = new;
+            }
+        }
+    };
+    renumber(screen.entities_mut());
+    renumber(screen.triggers_mut());
+
+    for old in shared {
+        if mapping.get(old).is_some() {
+            mapping.mark_ambiguous(old);
+        }
+    }
+
+    mapping.apply_to_references(screen.entities_mut(), reference_keys);
+    mapping.apply_to_references(screen.triggers_mut(), reference_keys);
+    mapping
+}
+
+/// Renumbers every use of an id after its first, returning the mapping for each changed screen.
+/// References to ids used more than once within a screen are left alone and listed in the
+/// mapping's [`IdMapping::ambiguous`]. As with [`renumber_screen`], references are only rewritten
+/// within the renumbered screen, so references from other screens stay with the first user.
+pub fn renumber_collisions(
+    map: &mut CelesteMap,
+    reference_keys: &[&str],
+) -> Vec<(String, IdMapping)> {
+    // Allocate from every existing id, so renumbered ids don't take one a later screen uses.
+    let mut allocator = IdAllocator::for_map(map);
+    let mut seen = HashSet::new();
+
+    let mut mappings = Vec::new();
+    for screen in map.screens_mut().iter_mut() {
+        let mapping = renumber_where(screen, &mut allocator, reference_keys, |_, id| {
+            !seen.insert(id)
+        });
+        if !mapping.is_empty() {
+            mappings.push((screen.name().to_string(), mapping));
+        }
+    }
+    mappings
+}
+
+#[cfg(test)]
+mod tests {
+    use fujiformer_geom::{Point, Rect};
+
+    use super::*;
+
+    const REFERENCES: &[&str] = &["target"];
+
+    fn entity(id: i32, target: Option<i32>) -> Entity {
+        let mut entity = Entity::new("switch".to_string(), id, Point::new(0, 0));
+        if let Some(target) = target {
+            let property = ("target".to_string(), Value::Int(target));
+            entity.properties_mut().push(property);
+        }
+        entity
+    }
+
+    fn screen(name: &str, entities: Vec<Entity>) -> Screen {
+        let mut screen = Screen::new(name.to_string(), Rect::from_edges(0, 0, 320, 184));
+        *screen.entities_mut() = entities;
+        screen
+    }
+
+    fn target(entity: &Entity) -> Option<i32> {
+        entity
+            .properties()
+            .iter()
+            .find_map(|(key, value)| match value {
+                Value::Int(x) if key == "target" => Some(*x),
+                _ => None,
+            })
+    }
+
+    fn ids(screen: &Screen) -> Vec<i32> {
+        screen.entities().iter().map(|x| x.id()).collect()
+    }
+
+    #[test]
+    fn allocates_above_used_ids() {
+        let mut allocator = IdAllocator::new([3, 7].iter().copied().collect());
+        assert_eq!(allocator.allocate(), 8);
+        assert!(allocator.reserve(9));
+        assert!(!allocator.reserve(3));
+        assert_eq!(allocator.allocate(), 10);
+        assert_eq!(IdAllocator::new(HashSet::new()).allocate(), 0);
+    }
+
+    #[test]
+    fn allocates_lowest_free_id_past_max() {
+        let used = [0, 1, 3, i32::MAX].iter().copied().collect();
+        let mut allocator = IdAllocator::new(used);
+        assert_eq!(allocator.allocate(), 2);
+        assert_eq!(allocator.allocate(), 4);
+
+        let mut allocator = IdAllocator::new([0, i32::MAX - 1].iter().copied().collect());
+        assert_eq!(allocator.allocate(), i32::MAX);
+        assert_eq!(allocator.allocate(), 1);
+    }
+
+    #[test]
+    fn renumber_screen_rewrites_references() {
+        let mut allocator = IdAllocator::new([1, 2].iter().copied().collect());
+        let mut pasted = screen("b", vec![entity(1, None), entity(5, Some(1))]);
+        let mapping = renumber_screen(&mut pasted, &mut allocator, REFERENCES);
+
+        assert_eq!(ids(&pasted), vec![3, 5]);
+        assert_eq!(target(&pasted.entities()[1]), Some(3));
+        assert_eq!(mapping.get(1), Some(3));
+        assert_eq!(mapping.len(), 1);
+        assert_eq!(mapping.ambiguous().count(), 0);
+        assert!(allocator.is_used(5));
+    }
+
+    #[test]
+    fn renumber_screen_leaves_ambiguous_references() {
+        let mut allocator = IdAllocator::new(HashSet::new());
+        let mut pasted = screen(
+            "a",
+            vec![entity(1, None), entity(1, None), entity(2, Some(1))],
+        );
+        let mapping = renumber_screen(&mut pasted, &mut allocator, REFERENCES);
+
+        assert_eq!(ids(&pasted), vec![1, 0, 2]);
+        assert_eq!(target(&pasted.entities()[2]), Some(1));
+        assert_eq!(mapping.ambiguous().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn renumber_collisions_keeps_first_user() {
+        let mut map = CelesteMap::new("test".to_string());
+        map.screens_mut()
+            .push(screen("a", vec![entity(1, None), entity(2, Some(1))]));
+        map.screens_mut()
+            .push(screen("b", vec![entity(1, None), entity(4, Some(1))]));
+        map.screens_mut().push(screen("c", vec![entity(5, None)]));
+        let mappings = renumber_collisions(&mut map, REFERENCES);
+
+        let screens = map.screens();
+        assert_eq!(ids(&screens[0]), vec![1, 2]);
+        assert_eq!(target(&screens[0].entities()[1]), Some(1));
+        // The new id is above every id in the map, including ones in later screens.
+        assert_eq!(ids(&screens[1]), vec![6, 4]);
+        assert_eq!(target(&screens[1].entities()[1]), Some(6));
+        assert_eq!(ids(&screens[2]), vec![5]);
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].0, "b");
+        assert!(find_collisions(&map).is_empty());
+    }
+
+    #[test]
+    fn renumber_collisions_at_max_id() {
+        let mut map = CelesteMap::new("test".to_string());
+        map.screens_mut()
+            .push(screen("a", vec![entity(i32::MAX, None), entity(0, None)]));
+        map.screens_mut()
+            .push(screen("b", vec![entity(i32::MAX, None), entity(1, None)]));
+        renumber_collisions(&mut map, REFERENCES);
+
+        assert_eq!(ids(&map.screens()[1]), vec![2, 1]);
+        assert!(find_collisions(&map).is_empty());
+    }
+}
@@ -1,31 +1,104 @@
-use std::io::{BufReader, Read};
+use std::{
+    collections::HashMap,
+    io::{BufReader, Read, Write},
+};
 
-use super::{raw::CelesteIo, value::Value};
+use super::{
+    node::Node,
+    raw::{CelesteIo, CelesteWrite, NonRleString, RleString, WriteError},
+    value::Value,
+};
 
 use thiserror::Error;
 
-pub struct Lookup(Vec<String>);
+pub struct Lookup {
+    strings: Vec<String>,
+    indices: HashMap<String, usize>,
+}
 
 impl Lookup {
     pub fn new(x: Vec<String>) -> Self {
-        Lookup(x)
+        let indices = x.iter().enumerate().map(|(i, x)| (x.clone(), i)).collect();
+        Lookup {
+            strings: x,
+            indices,
+        }
+    }
+
+    /// Collects the names and keys in `node`, along with string values that take fewer bytes
+    /// through the lookup than written inline, in first use order.
+    pub fn for_node(node: &Node) -> Self {
+        fn collect<'a>(
+            node: &'a Node,
+            lookup: &mut Lookup,
+            values: &mut Vec<&'a str>,
+            counts: &mut HashMap<&'a str, usize>,
+        ) {
+            lookup.insert(node.name());
+            for (key, value) in node.properties().iter() {
+                lookup.insert(key);
+                if let Value::String(x) = value {
+                    let count = counts.entry(x).or_insert(0);
+                    if *count == 0 {
+                        values.push(x);
+                    }
+                    *count += 1;
+                }
+            }
+            for child in node.children().iter() {
+                collect(child, lookup, values, counts);
+            }
+        }
+
+        let mut lookup = Lookup::new(Vec::new());
+        let (mut values, mut counts) = (Vec::new(), HashMap::new());
+        collect(node, &mut lookup, &mut values, &mut counts);
+
+        for x in values.into_iter() {
+            let count = counts[x];
+            let inline =
+                NonRleString::encoded_len(x).min(RleString::encoded_len(x).unwrap_or(usize::MAX));
+            if NonRleString::encoded_len(x) + 2 * count < inline * count {
+                lookup.insert(x);
+            }
+        }
+        lookup
+    }
+
+    fn insert(&mut self, x: &str) {
+        if !self.indices.contains_key(x) {
+            self.indices.insert(x.to_string(), self.strings.len());
+            self.strings.push(x.to_string());
+        }
     }
 
     pub fn as_ref(&self) -> LookupRef<'_> {
-        LookupRef(&self.0)
+        LookupRef(self)
     }
 }
 
 #[derive(Clone, Copy)]
-pub struct LookupRef<'a>(&'a [String]);
+pub struct LookupRef<'a>(&'a Lookup);
 
 impl<'a> LookupRef<'a> {
     pub fn get(&self, i: usize) -> Option<&str> {
-        self.0.get(i).map(String::as_str)
+        self.0.strings.get(i).map(String::as_str)
+    }
+
+    pub fn index_of(&self, x: &str) -> Option<usize> {
+        self.0.indices.get(x).copied()
+    }
+
+    pub fn strings(&self) -> &[String] {
+        &self.0.strings
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.0.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.strings.is_empty()
     }
 }
 
@@ -66,3 +139,20 @@ impl CelesteIo for LookupValue {
             })
     }
 }
+
+/// Writes the lookup index of a string.
+pub struct LookupIndex<'a>(pub &'a str);
+
+impl CelesteWrite for LookupIndex<'_> {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        let index = lookup
+            .ok_or(WriteError::MissingLookup)?
+            .index_of(self.0)
+            .ok_or_else(|| WriteError::NotInLookup(self.0.to_string()))?;
+        (index as u16).write(writer, None)
+    }
+}
@@ -4,8 +4,8 @@ mod raw;
 mod value;
 
 pub use self::{
-    lookup::{Lookup, LookupError, LookupRef},
+    lookup::{Lookup, LookupError, LookupIndex, LookupRef},
     node::{Node, NodeReadError},
-    raw::{CelesteIo, NonRleString, StringReadError},
+    raw::{CelesteIo, CelesteWrite, NonRleString, RleString, StringReadError, WriteError},
     value::{ReadValueError, Value},
 };
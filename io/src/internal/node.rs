@@ -1,19 +1,20 @@
 use std::{
+    convert::TryFrom,
     fmt::Display,
-    io::{BufReader, Read},
+    io::{BufReader, Read, Write},
 };
 
 use thiserror::Error;
 
 use super::{
-    lookup::{LookupError, LookupRef, LookupValue},
-    raw::CelesteIo,
+    lookup::{LookupError, LookupIndex, LookupRef, LookupValue},
+    raw::{CelesteIo, CelesteWrite, WriteError},
     value::ReadValueError,
 };
 
 use super::value::Value;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     name: String,
     properties: Vec<(String, Value)>,
@@ -135,3 +136,30 @@ impl CelesteIo for Node {
         Ok(node)
     }
 }
+
+impl CelesteWrite for Node {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        LookupIndex(&self.name).write(writer, lookup)?;
+
+        u8::try_from(self.properties.len())
+            .map_err(|_| WriteError::TooManyProperties(self.name.clone()))?
+            .write(writer, lookup)?;
+        for (key, value) in self.properties.iter() {
+            LookupIndex(key).write(writer, lookup)?;
+            value.write(writer, lookup)?;
+        }
+
+        u16::try_from(self.children.len())
+            .map_err(|_| WriteError::TooManyChildren(self.name.clone()))?
+            .write(writer, lookup)?;
+        for child in self.children.iter() {
+            child.write(writer, lookup)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Write};
 
 use thiserror::Error;
 
@@ -13,6 +13,30 @@ pub trait CelesteIo: Sized {
     ) -> Result<Self, Self::Error>;
 }
 
+pub trait CelesteWrite {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError>;
+}
+
+#[derive(Error, Debug)]
+pub enum WriteError {
+    #[error("failed to write bytes")]
+    Io(#[from] std::io::Error),
+    #[error("missing lookup")]
+    MissingLookup,
+    #[error("string {0:?} missing from lookup")]
+    NotInLookup(String),
+    #[error("string too long to encode")]
+    StringTooLong,
+    #[error("node {0} has more than 255 properties")]
+    TooManyProperties(String),
+    #[error("node {0} has more than 65535 children")]
+    TooManyChildren(String),
+}
+
 impl CelesteIo for bool {
     type Error = std::io::Error;
 
@@ -26,6 +50,17 @@ impl CelesteIo for bool {
     }
 }
 
+impl CelesteWrite for bool {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        _lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        writer.write_all(&[*self as u8])?;
+        Ok(())
+    }
+}
+
 macro_rules! impl_value_type_prim {
     ( $(( $size:literal, $x:ty )),* ) => {
         $(
@@ -41,6 +76,17 @@ macro_rules! impl_value_type_prim {
                     Ok(<$x>::from_le_bytes(buf))
                 }
             }
+
+            impl CelesteWrite for $x {
+                fn write<W: Write>(
+                    &self,
+                    writer: &mut W,
+                    _lookup: Option<LookupRef<'_>>,
+                ) -> Result<(), WriteError> {
+                    writer.write_all(&self.to_le_bytes())?;
+                    Ok(())
+                }
+            }
         )*
     };
 }
@@ -69,6 +115,24 @@ impl CelesteIo for StringLength {
     }
 }
 
+impl CelesteWrite for StringLength {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        let mut rest = self.0;
+        loop {
+            let byte = (rest & 0b0111_1111) as u8;
+            rest >>= 7;
+            if rest == 0 {
+                return byte.write(writer, lookup);
+            }
+            (byte | 0b1000_0000).write(writer, lookup)?;
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum StringReadError {
     #[error("failed to read string bytes")]
@@ -79,8 +143,42 @@ pub enum StringReadError {
 
 pub struct NonRleString(pub String);
 
+impl NonRleString {
+    /// Bytes needed to write `x`, including its length.
+    pub fn encoded_len(x: &str) -> usize {
+        let mut length_bytes = 1;
+        while x.len() >> (7 * length_bytes) != 0 {
+            length_bytes += 1;
+        }
+        length_bytes + x.len()
+    }
+}
+
 pub struct RleString(pub String);
 
+impl RleString {
+    /// Bytes needed to write `x`, including its length, or `None` if it is too long to encode.
+    pub fn encoded_len(x: &str) -> Option<usize> {
+        let runs = rle_runs(x).len();
+        if runs * 2 > u16::MAX as usize {
+            None
+        } else {
+            Some(2 + runs * 2)
+        }
+    }
+}
+
+fn rle_runs(x: &str) -> Vec<(u8, u8)> {
+    let mut runs: Vec<(u8, u8)> = Vec::new();
+    for &byte in x.as_bytes().iter() {
+        match runs.last_mut() {
+            Some((times, x)) if *x == byte && *times < u8::MAX => *times += 1,
+            _ => runs.push((1, byte)),
+        }
+    }
+    runs
+}
+
 impl From<NonRleString> for Value {
     fn from(x: NonRleString) -> Self {
         Value::String(x.0)
@@ -129,3 +227,35 @@ impl CelesteIo for RleString {
         Ok(RleString(String::from_utf8(bytes)?))
     }
 }
+
+impl CelesteWrite for NonRleString {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        StringLength(self.0.len()).write(writer, lookup)?;
+        writer.write_all(self.0.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl CelesteWrite for RleString {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        let runs = rle_runs(&self.0);
+        if runs.len() * 2 > u16::MAX as usize {
+            return Err(WriteError::StringTooLong);
+        }
+
+        ((runs.len() * 2) as u16).write(writer, lookup)?;
+        for (times, byte) in runs.into_iter() {
+            times.write(writer, lookup)?;
+            byte.write(writer, lookup)?;
+        }
+        Ok(())
+    }
+}
@@ -1,17 +1,17 @@
 use std::{
     convert::{TryFrom, TryInto},
     fmt::Display,
-    io::{BufReader, Read},
+    io::{BufReader, Read, Write},
 };
 
 use thiserror::Error;
 
 use super::{
-    lookup::{LookupError, LookupRef, LookupValue},
-    raw::{CelesteIo, NonRleString, RleString, StringReadError},
+    lookup::{LookupError, LookupIndex, LookupRef, LookupValue},
+    raw::{CelesteIo, CelesteWrite, NonRleString, RleString, StringReadError, WriteError},
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Bool(bool),
     Int(i32),
@@ -142,3 +142,49 @@ impl CelesteIo for Value {
         }
     }
 }
+
+/// Strings are written with whichever of the lookup, plain or run length encoding is smallest,
+/// where strings missing from the lookup are written inline.
+impl CelesteWrite for Value {
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        lookup: Option<LookupRef<'_>>,
+    ) -> Result<(), WriteError> {
+        match self {
+            Value::Bool(x) => {
+                0u8.write(writer, lookup)?;
+                x.write(writer, lookup)
+            }
+            Value::Int(x) => {
+                if let Ok(x) = u8::try_from(*x) {
+                    1u8.write(writer, lookup)?;
+                    x.write(writer, lookup)
+                } else if let Ok(x) = i16::try_from(*x) {
+                    2u8.write(writer, lookup)?;
+                    x.write(writer, lookup)
+                } else {
+                    3u8.write(writer, lookup)?;
+                    x.write(writer, lookup)
+                }
+            }
+            Value::Float(x) => {
+                4u8.write(writer, lookup)?;
+                x.write(writer, lookup)
+            }
+            Value::String(x) => {
+                let rle_len = RleString::encoded_len(x);
+                if matches!(lookup, Some(lookup) if lookup.index_of(x).is_some()) {
+                    5u8.write(writer, lookup)?;
+                    LookupIndex(x).write(writer, lookup)
+                } else if matches!(rle_len, Some(len) if len < NonRleString::encoded_len(x)) {
+                    7u8.write(writer, lookup)?;
+                    RleString(x.clone()).write(writer, lookup)
+                } else {
+                    6u8.write(writer, lookup)?;
+                    NonRleString(x.clone()).write(writer, lookup)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,471 @@
+//! Arranging screens so they don't overlap.
+//!
+//! Screens sharing an edge form a transition the player can cross, so they are kept together as a
+//! cluster and always moved as one. Every move is a whole number of tiles, so screens on the tile
+//! grid stay on it.
+
+use std::collections::HashSet;
+
+use fujiformer_geom::{IntPoint, IntRect, Point, Rect, Rounding, SpatialIndex, TILE_SIZE};
+
+use crate::CelesteMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenMove {
+    pub screen: String,
+    pub from: IntPoint,
+    pub to: IntPoint,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackOptions {
+    /// Pixels left between clusters.
+    pub gap: u32,
+    /// Width of each row of clusters, or `None` to aim for a square layout.
+    pub row_width: Option<u32>,
+}
+
+/// Whether two rects share an edge of positive length without overlapping.
+fn shares_edge(a: &IntRect, b: &IntRect) -> bool {
+    let horizontal_overlap = a.left() < b.right() && b.left() < a.right();
+    let vertical_overlap = a.top() < b.bottom() && b.top() < a.bottom();
+    (horizontal_overlap && (a.bottom() == b.top() || b.bottom() == a.top()))
+        || (vertical_overlap && (a.right() == b.left() || b.right() == a.left()))
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    if parents[i] != i {
+        parents[i] = find(parents, parents[i]);
+    }
+    parents[i]
+}
+
+/// Groups screens joined by shared edges, as lists of screen indices. A cluster whose screens
+/// overlap each other can't be separated while kept whole, so it's split into single screens.
+fn clusters(rects: &[IntRect]) -> Vec<Vec<usize>> {
+    let index: SpatialIndex<usize, i32, u32> = rects.iter().copied().enumerate().collect();
+    let mut parents: Vec<usize> = (0..rects.len()).collect();
+    for (i, rect) in rects.iter().enumerate() {
+        // Grow by one pixel so screens that only touch are found.
+        for j in index.query_rect(&rect.inflate(1, 1)) {
+            if shares_edge(rect, &rects[j]) {
+                let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                parents[a] = b;
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut cluster_of = vec![usize::MAX; rects.len()];
+    for i in 0..rects.len() {
+        let root = find(&mut parents, i);
+        if cluster_of[root] == usize::MAX {
+            cluster_of[root] = clusters.len();
+            clusters.push(Vec::new());
+        }
+        clusters[cluster_of[root]].push(i);
+    }
+
+    clusters
+        .into_iter()
+        .flat_map(|cluster| {
+            let overlapping = cluster.iter().any(|&i| {
+                cluster
+                    .iter()
+                    .any(|&j| i != j && rects[i].intersects(&rects[j]))
+            });
+            if overlapping {
+                cluster.into_iter().map(|x| vec![x]).collect()
+            } else {
+                vec![cluster]
+            }
+        })
+        .collect()
+}
+
+/// Rounds `x` to a multiple of the tile size, away from zero.
+fn round_to_tiles(x: i32) -> i32 {
+    let tile = TILE_SIZE as i32;
+    if x >= 0 {
+        (x + tile - 1).div_euclid(tile) * tile
+    } else {
+        -(-x + tile - 1).div_euclid(tile) * tile
+    }
+}
+
+/// Rounds `x` up to a multiple of the tile size.
+fn ceil_to_tiles(x: i32) -> i32 {
+    Rounding::Ceil.divide(x, TILE_SIZE as i32) * TILE_SIZE as i32
+}
+
+/// Offsets on one axis that would bring a member edge flush against an obstacle edge.
+fn candidate_offsets(
+    members: &[IntRect],
+    obstacles: &[IntRect],
+    edges: impl Fn(&IntRect) -> (i32, i32),
+) -> Vec<i32> {
+    let mut offsets = vec![0];
+    for member in members.iter() {
+        let (low, high) = edges(member);
+        for obstacle in obstacles.iter() {
+            let (obstacle_low, obstacle_high) = edges(obstacle);
+            offsets.push(round_to_tiles(obstacle_high - low));
+            offsets.push(round_to_tiles(obstacle_low - high));
+        }
+    }
+    offsets.sort_unstable();
+    offsets.dedup();
+    offsets
+}
+
+/// The shortest tile aligned offset moving every member clear of `obstacles`, which all lie
+/// within `extent`.
+///
+/// Only obstacles near the members are considered, in a window that widens until the best free
+/// offset found stays inside it, so the search costs about the same however big the map is.
+fn nearest_free_offset(
+    members: &[IntRect],
+    obstacles: &SpatialIndex<usize, i32, u32>,
+    extent: IntRect,
+) -> IntPoint {
+    let is_free = |offset: IntPoint| {
+        members
+            .iter()
+            .all(|x| obstacles.query_rect(&x.translate(offset)).is_empty())
+    };
+    let bounds = match Rect::bounding_box(members.iter().copied()) {
+        Some(x) if !is_free(Point::new(0, 0)) => x,
+        _ => return Point::new(0, 0),
+    };
+    let cost = |x: &IntPoint| (x.x() as i64).pow(2) + (x.y() as i64).pow(2);
+
+    let tile = TILE_SIZE as i32;
+    let mut reach = bounds
+        .size()
+        .width()
+        .max(bounds.size().height())
+        .max(TILE_SIZE) as i32;
+    loop {
+        // An extra tile finds obstacles the members would only touch, and covers rounding.
+        let window = bounds.inflate(reach.saturating_add(tile), reach.saturating_add(tile));
+        let nearby: Vec<IntRect> = obstacles
+            .query_rect(&window)
+            .into_iter()
+            .filter_map(|x| obstacles.get(x))
+            .collect();
+        let everything = window.contains_rect(&extent);
+
+        let xs = candidate_offsets(members, &nearby, |x| (x.left(), x.right()));
+        let ys = candidate_offsets(members, &nearby, |x| (x.top(), x.bottom()));
+        let mut offsets: Vec<IntPoint> = xs
+            .iter()
+            .flat_map(|&x| ys.iter().map(move |&y| Point::new(x, y)))
+            .collect();
+        offsets.sort_by_key(cost);
+
+        // A closer offset would be against an obstacle within reach, so would have been found.
+        // Once every obstacle is in the window, moving past them all on one axis is free.
+        match offsets.into_iter().find(|&x| is_free(x)) {
+            Some(x) if everything || cost(&x) <= (reach as i64).pow(2) => return x,
+            None if everything => return Point::new(0, 0),
+            _ => reach = reach.saturating_mul(2),
+        }
+    }
+}
+
+fn apply_offsets(map: &mut CelesteMap, offsets: &[IntPoint]) -> Vec<ScreenMove> {
+    let mut moves = Vec::new();
+    for (screen, &offset) in map.screens_mut().iter_mut().zip(offsets.iter()) {
+        if offset != Point::new(0, 0) {
+            let from = screen.shape().position();
+            *screen.shape_mut() = screen.shape().translate(offset);
+            moves.push(ScreenMove {
+                screen: screen.name().to_string(),
+                from,
+                to: screen.shape().position(),
+            });
+        }
+    }
+    moves
+}
+
+/// Moves screens until none overlap, keeping screens that share edges together.
+///
+/// Clusters containing a screen named in `pinned` never move. Other clusters are placed largest
+/// first, each moved the shortest distance that clears the clusters already placed, so the
+/// screens that move are usually the ones that were pasted over existing ones.
+///
+/// This is a greedy heuristic rather than a search for the smallest total movement. A cluster
+/// placed early never moves again to make room, so a later cluster may travel further than it
+/// would have to if both moved.
+pub fn resolve_overlaps(map: &mut CelesteMap, pinned: &[&str]) -> Vec<ScreenMove> {
+    let rects: Vec<IntRect> = map.screens().iter().map(|x| x.shape()).collect();
+    let pinned: HashSet<usize> = map
+        .screens()
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| pinned.contains(&x.name()))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut clusters = clusters(&rects);
+    let area = |cluster: &Vec<usize>| -> u64 {
+        cluster
+            .iter()
+            .map(|&i| rects[i].size().width() as u64 * rects[i].size().height() as u64)
+            .sum()
+    };
+    // Pinned clusters first, then by size, so small clusters move out of the way of large ones.
+    clusters.sort_by_key(|x| {
+        (
+            !x.iter().any(|i| pinned.contains(i)),
+            std::cmp::Reverse(area(x)),
+        )
+    });
+
+    let mut placed = SpatialIndex::new();
+    let mut extent: Option<IntRect> = None;
+    let mut offsets = vec![Point::new(0, 0); rects.len()];
+    for cluster in clusters.iter() {
+        let members: Vec<IntRect> = cluster.iter().map(|&i| rects[i]).collect();
+        let offset = match extent {
+            Some(extent) if !cluster.iter().any(|i| pinned.contains(i)) => {
+                nearest_free_offset(&members, &placed, extent)
+            }
+            _ => Point::new(0, 0),
+        };
+        for &i in cluster.iter() {
+            offsets[i] = offset;
+            let rect = rects[i].translate(offset);
+            placed.insert(i, rect);
+            extent = Some(extent.map_or(rect, |x| x.union(&rect)));
+        }
+    }
+
+    apply_offsets(map, &offsets)
+}
+
+/// Packs screens tightly into rows, such as for a test map of unrelated screens. Screens that
+/// share edges stay together.
+pub fn pack(map: &mut CelesteMap, options: &PackOptions) -> Vec<ScreenMove> {
+    let rects: Vec<IntRect> = map.screens().iter().map(|x| x.shape()).collect();
+    let mut clusters: Vec<(Vec<usize>, IntRect)> = clusters(&rects)
+        .into_iter()
+        .filter_map(|x| {
+            let bounds = Rect::bounding_box(x.iter().map(|&i| rects[i]))?;
+            Some((x, bounds))
+        })
+        .collect();
+    // Tallest first keeps rows from wasting height.
+    clusters.sort_by_key(|(_, bounds)| std::cmp::Reverse(bounds.size().height()));
+
+    let gap = ceil_to_tiles(options.gap as i32);
+    let row_width = options.row_width.map_or_else(
+        || {
+            let area: f64 = clusters
+                .iter()
+                .map(|(_, x)| (x.size().width() as f64 + gap as f64) * x.size().height() as f64)
+                .sum();
+            area.sqrt() as i32
+        },
+        |x| x as i32,
+    );
+    let origin =
+        Rect::bounding_box(rects.iter().copied()).map_or(Point::new(0, 0), |x| x.position());
+
+    let mut offsets = vec![Point::new(0, 0); rects.len()];
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for (cluster, bounds) in clusters.iter() {
+        // Moving whole tiles keeps the cluster's offset from the grid, so it lands up to a tile
+        // past its slot, and the slot is widened to make room.
+        let misalignment = bounds.position() - origin;
+        let width = ceil_to_tiles(
+            misalignment.x().rem_euclid(TILE_SIZE as i32) + bounds.size().width() as i32,
+        );
+        let height = ceil_to_tiles(
+            misalignment.y().rem_euclid(TILE_SIZE as i32) + bounds.size().height() as i32,
+        );
+        if x > 0 && x + width > row_width {
+            x = 0;
+            y += row_height + gap;
+            row_height = 0;
+        }
+        let target = origin + Point::new(x, y);
+        let offset = Point::new(
+            ceil_to_tiles(target.x() - bounds.left()),
+            ceil_to_tiles(target.y() - bounds.top()),
+        );
+        for &i in cluster.iter() {
+            offsets[i] = offset;
+        }
+        x += width + gap;
+        row_height = row_height.max(height);
+    }
+
+    apply_offsets(map, &offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Screen;
+
+    fn map(screens: &[IntRect]) -> CelesteMap {
+        let mut map = CelesteMap::new("test".to_string());
+        for (i, &rect) in screens.iter().enumerate() {
+            map.screens_mut()
+                .push(Screen::new(format!("lvl_{}", i), rect));
+        }
+        map
+    }
+
+    fn shapes(map: &CelesteMap) -> Vec<IntRect> {
+        map.screens().iter().map(|x| x.shape()).collect()
+    }
+
+    fn assert_separate(rects: &[IntRect]) {
+        for (i, a) in rects.iter().enumerate() {
+            for b in rects[i + 1..].iter() {
+                assert!(!a.intersects(b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    fn assert_on_grid(rects: &[IntRect]) {
+        for rect in rects.iter() {
+            assert_eq!(rect.left() % TILE_SIZE as i32, 0, "{:?}", rect);
+            assert_eq!(rect.top() % TILE_SIZE as i32, 0, "{:?}", rect);
+        }
+    }
+
+    #[test]
+    fn separate_screens_dont_move() {
+        let mut map = map(&[
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(400, 0, 720, 184),
+        ]);
+        assert!(resolve_overlaps(&mut map, &[]).is_empty());
+    }
+
+    #[test]
+    fn pasted_screen_moves_the_shortest_way_out() {
+        let mut map = map(&[
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(296, 40, 456, 120),
+        ]);
+        let moves = resolve_overlaps(&mut map, &[]);
+
+        assert_eq!(
+            moves,
+            vec![ScreenMove {
+                screen: "lvl_1".to_string(),
+                from: Point::new(296, 40),
+                to: Point::new(320, 40),
+            }]
+        );
+    }
+
+    #[test]
+    fn pinned_screens_stay_put() {
+        let mut map = map(&[
+            Rect::from_edges(0, 0, 160, 160),
+            Rect::from_edges(40, 0, 360, 184),
+        ]);
+        let moves = resolve_overlaps(&mut map, &["lvl_0"]);
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].screen, "lvl_1");
+        assert_eq!(shapes(&map)[0], Rect::from_edges(0, 0, 160, 160));
+        assert_separate(&shapes(&map));
+    }
+
+    #[test]
+    fn resolves_many_overlaps() {
+        let rects: Vec<IntRect> = (0..30)
+            .map(|i| {
+                let (x, y) = ((i % 6) * 96, (i / 6) * 64);
+                Rect::from_edges(x, y, x + 320, y + 184)
+            })
+            .collect();
+        let mut map = map(&rects);
+        resolve_overlaps(&mut map, &[]);
+
+        assert_separate(&shapes(&map));
+        assert_on_grid(&shapes(&map));
+    }
+
+    #[test]
+    fn transitions_stay_adjacent() {
+        let mut map = map(&[
+            Rect::from_edges(0, 0, 640, 368),
+            // A pair sharing an edge, pasted over the first screen.
+            Rect::from_edges(600, 100, 920, 284),
+            Rect::from_edges(920, 100, 1240, 284),
+        ]);
+        let moves = resolve_overlaps(&mut map, &[]);
+        let rects = shapes(&map);
+
+        assert_eq!(moves.len(), 2);
+        assert_separate(&rects);
+        assert!(shares_edge(&rects[1], &rects[2]));
+        assert_eq!(rects[2].left(), rects[1].right());
+        assert_eq!(rects[2].top(), rects[1].top());
+    }
+
+    #[test]
+    fn pack_fills_rows_compactly() {
+        let rects: Vec<IntRect> = (0..9)
+            .map(|i| {
+                let x = i * 1000;
+                Rect::from_edges(x, -x, x + 320, -x + 184)
+            })
+            .collect();
+        let mut map = map(&rects);
+        pack(&mut map, &PackOptions::default());
+        let rects = shapes(&map);
+
+        assert_separate(&rects);
+        assert_on_grid(&rects);
+        // Equal screens leave no space between them but the end of the last row.
+        let bounds = Rect::bounding_box(rects.iter().copied()).unwrap();
+        let (width, height) = (bounds.size().width(), bounds.size().height());
+        assert_eq!(width % 320, 0);
+        assert_eq!(height % 184, 0);
+        assert!((width / 320) * (height / 184) < 9 + width / 320);
+        assert!(width <= 2 * height && height <= 2 * width);
+    }
+
+    #[test]
+    fn pack_keeps_gap_and_row_width() {
+        let rects: Vec<IntRect> = (0..4)
+            .map(|i| Rect::from_edges(i * 512, 0, i * 512 + 320, 184))
+            .collect();
+        let mut map = map(&rects);
+        let options = PackOptions {
+            gap: 16,
+            row_width: Some(700),
+        };
+        pack(&mut map, &options);
+        let rects = shapes(&map);
+
+        assert_separate(&rects);
+        let bounds = Rect::bounding_box(rects.iter().copied()).unwrap();
+        assert_eq!(bounds.size().width(), 2 * 320 + 16);
+        assert_eq!(bounds.size().height(), 2 * 184 + 16);
+    }
+
+    #[test]
+    fn pack_keeps_transitions_together() {
+        let mut map = map(&[
+            Rect::from_edges(0, 0, 320, 184),
+            Rect::from_edges(320, 0, 640, 184),
+            Rect::from_edges(5000, 5000, 5320, 5184),
+        ]);
+        pack(&mut map, &PackOptions::default());
+        let rects = shapes(&map);
+
+        assert_separate(&rects);
+        assert_eq!(rects[1].left(), rects[0].right());
+        assert_eq!(rects[1].top(), rects[0].top());
+    }
+}
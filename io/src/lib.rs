@@ -1,9 +1,20 @@
+mod entity;
 mod filler;
 mod map;
 mod screen;
+mod tiles;
 
+pub mod autofill;
+pub mod diff;
+pub mod ids;
 pub mod internal;
+pub mod layout;
+pub mod lint;
+pub mod merge;
+pub mod normalise;
 
+pub use entity::Entity;
 pub use filler::Filler;
-pub use map::{CelesteMap, CelesteMapReadError};
+pub use map::{CelesteMap, CelesteMapReadError, CelesteMapWriteError};
 pub use screen::Screen;
+pub use tiles::{Tiles, AIR};
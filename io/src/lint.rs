@@ -0,0 +1,337 @@
+//! Validation of a [`CelesteMap`] against the rules the game relies on.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use fujiformer_geom::{Point, ScreenPixels, SpatialIndex, TILE_SIZE};
+
+use crate::{CelesteMap, Screen};
+
+/// Screens smaller than this leave the camera showing outside the room.
+pub const MIN_SCREEN_WIDTH: u32 = 320;
+pub const MIN_SCREEN_HEIGHT: u32 = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Where in the map a [`Diagnostic`] was raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Screen { screen: String },
+    Entity { screen: String, id: i32 },
+    Filler { index: usize },
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Screen { screen } => write!(f, "screen {}", screen),
+            Location::Entity { screen, id } => write!(f, "screen {} entity {}", screen, id),
+            Location::Filler { index } => write!(f, "filler {}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    OverlappingScreens {
+        other: String,
+    },
+    MisalignedScreen,
+    UndersizedScreen {
+        width: u32,
+        height: u32,
+    },
+    DuplicateScreenName,
+    EntityOutsideScreen,
+    /// The node at `node` in the entity's list of nodes.
+    NodeOutsideScreen {
+        node: usize,
+    },
+    MissingPlayerSpawn,
+    DuplicateEntityId {
+        other_screen: String,
+    },
+    FillerOverlapsScreen {
+        screen: String,
+    },
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::OverlappingScreens { .. }
+            | Lint::DuplicateScreenName
+            | Lint::DuplicateEntityId { .. } => Severity::Error,
+            Lint::MisalignedScreen
+            | Lint::UndersizedScreen { .. }
+            | Lint::EntityOutsideScreen
+            | Lint::NodeOutsideScreen { .. }
+            | Lint::MissingPlayerSpawn
+            | Lint::FillerOverlapsScreen { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lint::OverlappingScreens { other } => write!(f, "overlaps screen {}", other),
+            Lint::MisalignedScreen => write!(f, "not aligned to the {}px grid", TILE_SIZE),
+            Lint::UndersizedScreen { width, height } => write!(
+                f,
+                "size {}x{} is smaller than {}x{}",
+                width, height, MIN_SCREEN_WIDTH, MIN_SCREEN_HEIGHT
+            ),
+            Lint::DuplicateScreenName => write!(f, "name is used by another screen"),
+            Lint::EntityOutsideScreen => write!(f, "positioned outside its screen"),
+            Lint::NodeOutsideScreen { node } => {
+                write!(f, "node {} positioned outside its screen", node)
+            }
+            Lint::MissingPlayerSpawn => write!(f, "has no player spawn"),
+            Lint::DuplicateEntityId { other_screen } => {
+                write!(f, "id is also used in screen {}", other_screen)
+            }
+            Lint::FillerOverlapsScreen { screen } => write!(f, "overlaps screen {}", screen),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    location: Location,
+    lint: Lint,
+}
+
+impl Diagnostic {
+    pub fn new(location: Location, lint: Lint) -> Self {
+        Diagnostic { location, lint }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn lint(&self) -> &Lint {
+        &self.lint
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: {}", self.severity(), self.location, self.lint)
+    }
+}
+
+/// Runs every check over `map`, returning diagnostics in a stable order.
+pub fn lint(map: &CelesteMap) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let screen_index = screen_index(map);
+    lint_screen_shapes(map, &screen_index, &mut diagnostics);
+    lint_screen_names(map, &mut diagnostics);
+    lint_entities(map, &mut diagnostics);
+    lint_fillers(map, &screen_index, &mut diagnostics);
+    diagnostics
+}
+
+fn screen_location(screen: &Screen) -> Location {
+    Location::Screen {
+        screen: screen.name().to_string(),
+    }
+}
+
+fn screen_index(map: &CelesteMap) -> SpatialIndex<usize, i32, u32> {
+    map.screens()
+        .iter()
+        .enumerate()
+        .map(|(i, screen)| (i, screen.shape()))
+        .collect()
+}
+
+fn lint_screen_shapes(
+    map: &CelesteMap,
+    index: &SpatialIndex<usize, i32, u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let screens = map.screens();
+    for (i, screen) in screens.iter().enumerate() {
+        let rect = screen.shape();
+        let (pos, size) = (rect.position(), rect.size());
+
+        // Screen positions and sizes must line up with the tile grid.
+        if pos.x() % TILE_SIZE as i32 != 0
+            || pos.y() % TILE_SIZE as i32 != 0
+            || size.width() % TILE_SIZE != 0
+            || size.height() % TILE_SIZE != 0
+        {
+            diagnostics.push(Diagnostic::new(
+                screen_location(screen),
+                Lint::MisalignedScreen,
+            ));
+        }
+
+        if size.width() < MIN_SCREEN_WIDTH || size.height() < MIN_SCREEN_HEIGHT {
+            diagnostics.push(Diagnostic::new(
+                screen_location(screen),
+                Lint::UndersizedScreen {
+                    width: size.width(),
+                    height: size.height(),
+                },
+            ));
+        }
+
+        let mut others = index.query_rect(&rect);
+        others.retain(|&j| j > i);
+        others.sort_unstable();
+        for j in others {
+            diagnostics.push(Diagnostic::new(
+                screen_location(screen),
+                Lint::OverlappingScreens {
+                    other: screens[j].name().to_string(),
+                },
+            ));
+        }
+    }
+}
+
+fn lint_screen_names(map: &CelesteMap, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for screen in map.screens().iter() {
+        if !seen.insert(screen.name()) && reported.insert(screen.name()) {
+            diagnostics.push(Diagnostic::new(
+                screen_location(screen),
+                Lint::DuplicateScreenName,
+            ));
+        }
+    }
+}
+
+fn lint_entities(map: &CelesteMap, diagnostics: &mut Vec<Diagnostic>) {
+    let mut ids = HashMap::new();
+    for screen in map.screens().iter() {
+        // Entities can sit on the right and bottom edges, so the bounds are closed.
+        let size = screen.shape().size();
+        let inside = |point: Point<i32, ScreenPixels>| {
+            point.x() >= 0
+                && point.y() >= 0
+                && point.x() as u32 <= size.width()
+                && point.y() as u32 <= size.height()
+        };
+
+        if !screen.entities().iter().any(|x| x.name() == "player") {
+            diagnostics.push(Diagnostic::new(
+                screen_location(screen),
+                Lint::MissingPlayerSpawn,
+            ));
+        }
+
+        for entity in screen.entities().iter().chain(screen.triggers().iter()) {
+            let location = Location::Entity {
+                screen: screen.name().to_string(),
+                id: entity.id(),
+            };
+
+            if !inside(entity.position()) {
+                diagnostics.push(Diagnostic::new(location.clone(), Lint::EntityOutsideScreen));
+            }
+            for (node, position) in entity.nodes().iter().enumerate() {
+                if !inside(*position) {
+                    diagnostics.push(Diagnostic::new(
+                        location.clone(),
+                        Lint::NodeOutsideScreen { node },
+                    ));
+                }
+            }
+
+            if let Some(other_screen) = ids.insert(entity.id(), screen.name()) {
+                diagnostics.push(Diagnostic::new(
+                    location,
+                    Lint::DuplicateEntityId {
+                        other_screen: other_screen.to_string(),
+                    },
+                ));
+            }
+        }
+    }
+}
+
+fn lint_fillers(
+    map: &CelesteMap,
+    screen_index: &SpatialIndex<usize, i32, u32>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (index, filler) in map.fillers().iter().enumerate() {
+        let mut screens = screen_index.query_rect(&filler.pixel_shape());
+        screens.sort_unstable();
+        for i in screens {
+            diagnostics.push(Diagnostic::new(
+                Location::Filler { index },
+                Lint::FillerOverlapsScreen {
+                    screen: map.screens()[i].name().to_string(),
+                },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fujiformer_geom::Rect;
+
+    use super::*;
+    use crate::Entity;
+
+    fn outside_lints(positions: &[(i32, i32)]) -> Vec<Lint> {
+        let mut screen = Screen::new("a".to_string(), Rect::from_edges(0, 0, 320, 184));
+        let mut player = Entity::new("player".to_string(), 0, Point::new(8, 8));
+        *player.nodes_mut() = positions.iter().map(|&(x, y)| Point::new(x, y)).collect();
+        screen.entities_mut().push(player);
+        let mut map = CelesteMap::new("test".to_string());
+        map.screens_mut().push(screen);
+
+        lint(&map)
+            .into_iter()
+            .map(|x| x.lint().clone())
+            .filter(|x| {
+                matches!(
+                    x,
+                    Lint::EntityOutsideScreen | Lint::NodeOutsideScreen { .. }
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn positions_on_the_edges_are_inside() {
+        assert!(outside_lints(&[(0, 0), (320, 184), (320, 0), (0, 184)]).is_empty());
+    }
+
+    #[test]
+    fn positions_past_the_edges_are_outside() {
+        assert_eq!(
+            outside_lints(&[(321, 0), (0, 185), (-1, 0), (0, -1), (160, 92)]),
+            (0..4)
+                .map(|node| Lint::NodeOutsideScreen { node })
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,15 +1,25 @@
-use std::io::{BufReader, Read};
+use std::{
+    convert::TryFrom,
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
 
 use thiserror::Error;
 
 use crate::{
-    filler::{decode_fillers, Filler, FillersDecodeError},
-    internal::{CelesteIo, Lookup, LookupRef, Node, NodeReadError, NonRleString, StringReadError},
-    screen::{decode_screens, ScreensDecodeError},
+    filler::{decode_fillers, encode_fillers, Filler, FillersDecodeError},
+    internal::{
+        CelesteIo, CelesteWrite, Lookup, LookupRef, Node, NodeReadError, NonRleString,
+        StringReadError, WriteError,
+    },
+    normalise::{normalise_node, NormaliseOptions},
+    screen::{decode_screens, encode_screens, ScreensDecodeError},
     Screen,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CelesteMap {
     name: String,
     pub(crate) unread: Node,
@@ -27,10 +37,64 @@ impl CelesteMap {
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn read<R: Read>(mut reader: BufReader<R>) -> Result<Self, CelesteMapReadError> {
         <CelesteMap as CelesteIo>::read(&mut reader, None)
     }
 
+    /// Reads the map from the file at `path`.
+    pub fn open(path: &Path) -> Result<Self, CelesteMapReadError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), CelesteMapWriteError> {
+        write_map_node(&self.name, &self.to_node(), writer)
+    }
+
+    /// Writes the map after normalising its whole node tree.
+    pub fn write_normalised<W: Write>(
+        &self,
+        writer: W,
+        options: &NormaliseOptions<'_>,
+    ) -> Result<(), CelesteMapWriteError> {
+        let mut node = self.to_node();
+        normalise_node(&mut node, options);
+        write_map_node(&self.name, &node, writer)
+    }
+
+    /// Writes the map to `path` in canonical form, leaving the file untouched if writing fails.
+    ///
+    /// The map is written to a temporary file beside `path` that then replaces it, and any file
+    /// already at `path` is first copied to a `.bak` file beside it.
+    pub fn save(&self, path: &Path) -> Result<(), CelesteMapWriteError> {
+        let temp_path = sibling_path(path, ".tmp");
+        let result = (|| {
+            let file = File::create(&temp_path)?;
+            self.write_normalised(&file, &NormaliseOptions::canonical())?;
+            file.sync_all()?;
+            if path.exists() {
+                fs::copy(path, sibling_path(path, ".bak"))?;
+            }
+            fs::rename(&temp_path, path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// Encodes the map back into the node tree it was read from.
+    pub fn to_node(&self) -> Node {
+        let mut node = self.unread.clone();
+        node.push_child(encode_fillers(self));
+        node.push_child(encode_screens(self));
+        node
+    }
+
     pub fn fillers(&self) -> &[Filler] {
         &self.fillers
     }
@@ -68,6 +132,45 @@ pub enum CelesteMapReadError {
     ScreensDecodeError(#[from] ScreensDecodeError),
 }
 
+/// `path` with `suffix` added to the end of its file name.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map_or_else(OsString::new, OsString::from);
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn write_map_node<W: Write>(
+    name: &str,
+    node: &Node,
+    writer: W,
+) -> Result<(), CelesteMapWriteError> {
+    let mut writer = BufWriter::new(writer);
+    let lookup = Lookup::for_node(node);
+    let lookup = lookup.as_ref();
+    let count = u16::try_from(lookup.len()).map_err(|_| CelesteMapWriteError::LookupTooLarge)?;
+
+    NonRleString("CELESTE MAP".into()).write(&mut writer, None)?;
+    NonRleString(name.to_string()).write(&mut writer, None)?;
+    count.write(&mut writer, None)?;
+    for x in lookup.strings().iter() {
+        NonRleString(x.clone()).write(&mut writer, None)?;
+    }
+    node.write(&mut writer, Some(lookup))?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum CelesteMapWriteError {
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("too many distinct strings for lookup")]
+    LookupTooLarge,
+    #[error("node write error")]
+    NodeWriteError(#[from] WriteError),
+}
+
 impl CelesteIo for CelesteMap {
     type Error = CelesteMapReadError;
 
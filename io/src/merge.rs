@@ -0,0 +1,1070 @@
+//! Three way merging of [`CelesteMap`]s.
+//!
+//! Changes made on only one side are applied automatically. Where both sides changed the same
+//! thing differently, the result keeps `ours` and a [`Conflict`] is reported.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use fujiformer_geom::{
+    IntPoint, IntRect, IntSize, Point, Rect, ScreenPixels, ScreenTiles, TILE_SIZE,
+};
+
+use crate::{
+    diff::{EntityKind, TileLayer},
+    ids::{renumber_where, used_ids, IdAllocator},
+    internal::{Node, Value},
+    CelesteMap, Entity, Screen, Tiles, AIR,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Ours => write!(f, "ours"),
+            Side::Theirs => write!(f, "theirs"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// Both sides added a screen with this name, with different contents.
+    ScreenAdded { screen: String },
+    /// One side deleted a screen the other side modified.
+    ScreenDeleted { screen: String, deleted_by: Side },
+    ScreenMoved {
+        screen: String,
+        ours: IntPoint,
+        theirs: IntPoint,
+    },
+    ScreenResized {
+        screen: String,
+        ours: IntSize,
+        theirs: IntSize,
+    },
+    /// A screen setting, or a map setting when `screen` is `None`.
+    Setting {
+        screen: Option<String>,
+        key: String,
+        ours: Option<Value>,
+        theirs: Option<Value>,
+    },
+    /// Cells, in tiles relative to the screen, painted differently on both sides.
+    Tiles {
+        screen: String,
+        layer: TileLayer,
+        cells: Vec<Point<i32, ScreenTiles>>,
+    },
+    /// A side resized the screen by part of a tile, so its tiles can't be lined up with the
+    /// others' and only `ours` are kept.
+    TilesUnaligned { screen: String, layer: TileLayer },
+    /// Both sides added an entity with this id, differently. Additions by `theirs` are
+    /// renumbered when their ids are taken, so this only happens when an id was already shared.
+    EntityAdded {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+    },
+    EntityDeleted {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        deleted_by: Side,
+    },
+    EntityRenamed {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        ours: String,
+        theirs: String,
+    },
+    EntityMoved {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        ours: Point<i32, ScreenPixels>,
+        theirs: Point<i32, ScreenPixels>,
+    },
+    EntityNodes {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        ours: Vec<Point<i32, ScreenPixels>>,
+        theirs: Vec<Point<i32, ScreenPixels>>,
+    },
+    EntityProperty {
+        screen: String,
+        kind: EntityKind,
+        id: i32,
+        key: String,
+        ours: Option<Value>,
+        theirs: Option<Value>,
+    },
+    /// Data the merge doesn't understand, such as decals or styles, changed on both sides.
+    Unmodelled {
+        screen: Option<String>,
+        node: String,
+    },
+}
+
+fn option_to_string(x: &Option<Value>) -> String {
+    x.as_ref()
+        .map_or_else(|| "unset".to_string(), Value::to_string)
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::ScreenAdded { screen } => {
+                write!(f, "screen {}: added differently on both sides", screen)
+            }
+            Conflict::ScreenDeleted { screen, deleted_by } => write!(
+                f,
+                "screen {}: deleted by {}, modified by the other side",
+                screen, deleted_by
+            ),
+            Conflict::ScreenMoved {
+                screen,
+                ours,
+                theirs,
+            } => write!(f, "screen {}: moved to {:?} and {:?}", screen, ours, theirs),
+            Conflict::ScreenResized {
+                screen,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "screen {}: resized to {:?} and {:?}",
+                screen, ours, theirs
+            ),
+            Conflict::Setting {
+                screen,
+                key,
+                ours,
+                theirs,
+            } => {
+                match screen {
+                    Some(screen) => write!(f, "screen {}: ", screen)?,
+                    None => write!(f, "map: ")?,
+                }
+                write!(
+                    f,
+                    "{} set to {} and {}",
+                    key,
+                    option_to_string(ours),
+                    option_to_string(theirs)
+                )
+            }
+            Conflict::Tiles {
+                screen,
+                layer,
+                cells,
+            } => write!(
+                f,
+                "screen {}: {} {} tiles painted on both sides",
+                screen,
+                cells.len(),
+                layer
+            ),
+            Conflict::TilesUnaligned { screen, layer } => write!(
+                f,
+                "screen {}: {} tiles can't be lined up after resizing by part of a tile",
+                screen, layer
+            ),
+            Conflict::EntityAdded { screen, kind, id } => write!(
+                f,
+                "screen {}: {} {} added differently on both sides",
+                screen, kind, id
+            ),
+            Conflict::EntityDeleted {
+                screen,
+                kind,
+                id,
+                deleted_by,
+            } => write!(
+                f,
+                "screen {}: {} {} deleted by {}, modified by the other side",
+                screen, kind, id, deleted_by
+            ),
+            Conflict::EntityRenamed {
+                screen,
+                kind,
+                id,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "screen {}: {} {} changed type to {} and {}",
+                screen, kind, id, ours, theirs
+            ),
+            Conflict::EntityMoved {
+                screen,
+                kind,
+                id,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "screen {}: {} {} moved to {:?} and {:?}",
+                screen, kind, id, ours, theirs
+            ),
+            Conflict::EntityNodes {
+                screen,
+                kind,
+                id,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "screen {}: {} {} nodes changed to {:?} and {:?}",
+                screen, kind, id, ours, theirs
+            ),
+            Conflict::EntityProperty {
+                screen,
+                kind,
+                id,
+                key,
+                ours,
+                theirs,
+            } => write!(
+                f,
+                "screen {}: {} {} {} set to {} and {}",
+                screen,
+                kind,
+                id,
+                key,
+                option_to_string(ours),
+                option_to_string(theirs)
+            ),
+            Conflict::Unmodelled {
+                screen: Some(screen),
+                node,
+            } => write!(f, "screen {}: {} changed on both sides", screen, node),
+            Conflict::Unmodelled { screen: None, node } => {
+                write!(f, "map: {} changed on both sides", node)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    map: CelesteMap,
+    conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// The merged map, holding `ours` wherever there was a conflict.
+    pub fn map(&self) -> &CelesteMap {
+        &self.map
+    }
+
+    pub fn into_map(self) -> CelesteMap {
+        self.map
+    }
+
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Picks the side that changed, or `None` if both changed differently.
+fn three_way<'a, T: PartialEq + ?Sized>(base: &T, ours: &'a T, theirs: &'a T) -> Option<&'a T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+/// Merges the changes from `base` to `theirs` into `ours`.
+///
+/// Entities and triggers `theirs` added with an id `ours` also uses are given fresh ids first,
+/// unless they're identical to `ours`, so concurrent additions are both kept. References to them
+/// aren't rewritten, see [`merge_with_references`].
+pub fn merge(base: &CelesteMap, ours: &CelesteMap, theirs: &CelesteMap) -> MergeResult {
+    merge_with_references(base, ours, theirs, &[])
+}
+
+/// Like [`merge`], also rewriting integer properties named in `reference_keys` that refer to an
+/// entity `theirs` added, when it's given a fresh id. As with [`crate::ids::renumber_screen`],
+/// only references within the entity's screen are rewritten.
+pub fn merge_with_references(
+    base: &CelesteMap,
+    ours: &CelesteMap,
+    theirs: &CelesteMap,
+    reference_keys: &[&str],
+) -> MergeResult {
+    let theirs = &renumber_added(base, ours, theirs, reference_keys);
+    let mut conflicts = Vec::new();
+    let mut map = ours.clone();
+
+    map.unread = merge_unread(
+        &base.unread,
+        &ours.unread,
+        &theirs.unread,
+        None,
+        &mut conflicts,
+    );
+
+    // Fillers have no identity, so they merge as sets of rects.
+    map.fillers_mut()
+        .retain(|x| theirs.fillers().contains(x) || !base.fillers().contains(x));
+    for filler in theirs.fillers().iter() {
+        if !base.fillers().contains(filler) && !map.fillers().contains(filler) {
+            map.fillers_mut().push(filler.clone());
+        }
+    }
+
+    *map.screens_mut() = merge_screens(
+        base.screens(),
+        ours.screens(),
+        theirs.screens(),
+        &mut conflicts,
+    );
+
+    MergeResult { map, conflicts }
+}
+
+/// Renumbers the entities and triggers `theirs` added with ids `ours` uses for something else,
+/// so they aren't mistaken for the same entity or left sharing an id.
+fn renumber_added(
+    base: &CelesteMap,
+    ours: &CelesteMap,
+    theirs: &CelesteMap,
+    reference_keys: &[&str],
+) -> CelesteMap {
+    let base_ids = used_ids(base);
+    let ours_ids = used_ids(ours);
+    let mut allocator = IdAllocator::for_map(ours);
+    for id in base_ids.iter().chain(used_ids(theirs).iter()) {
+        allocator.reserve(*id);
+    }
+
+    let mut theirs = theirs.clone();
+    for screen in theirs.screens_mut().iter_mut() {
+        let ours_screen = ours.screens().iter().find(|x| x.name() == screen.name());
+        let same_in_ours = |kind: EntityKind, entity: &Entity| {
+            let ours_entities = match (ours_screen, kind) {
+                (Some(x), EntityKind::Entity) => x.entities(),
+                (Some(x), EntityKind::Trigger) => x.triggers(),
+                (None, _) => &[],
+            };
+            ours_entities.contains(entity)
+        };
+        let lists = [
+            (EntityKind::Entity, screen.entities()),
+            (EntityKind::Trigger, screen.triggers()),
+        ];
+        let colliding: HashSet<i32> = lists
+            .iter()
+            .flat_map(|(kind, entities)| entities.iter().map(move |x| (*kind, x)))
+            .filter(|(kind, x)| {
+                !base_ids.contains(&x.id()) && ours_ids.contains(&x.id()) && !same_in_ours(*kind, x)
+            })
+            .map(|(_, x)| x.id())
+            .collect();
+        if !colliding.is_empty() {
+            renumber_where(screen, &mut allocator, reference_keys, |_, id| {
+                colliding.contains(&id)
+            });
+        }
+    }
+    theirs
+}
+
+/// Merges property lists key by key, keeping the order of `ours`.
+fn merge_properties(
+    base: &[(String, Value)],
+    ours: &[(String, Value)],
+    theirs: &[(String, Value)],
+    mut on_conflict: impl FnMut(String, Option<Value>, Option<Value>),
+) -> Vec<(String, Value)> {
+    let to_map = |x: &[(String, Value)]| -> HashMap<String, Value> { x.iter().cloned().collect() };
+    let (base_map, ours_map, theirs_map) = (to_map(base), to_map(ours), to_map(theirs));
+
+    let mut keys: Vec<&String> = ours.iter().map(|(k, _)| k).collect();
+    for (key, _) in theirs.iter().chain(base.iter()) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut result = Vec::new();
+    for key in keys.into_iter() {
+        let (b, o, t) = (base_map.get(key), ours_map.get(key), theirs_map.get(key));
+        let merged = match three_way(&b, &o, &t) {
+            Some(x) => *x,
+            None => {
+                on_conflict(key.clone(), o.cloned(), t.cloned());
+                o
+            }
+        };
+        if let Some(value) = merged {
+            result.push((key.clone(), value.clone()));
+        }
+    }
+    result
+}
+
+/// Merges the properties and children of a node the merge doesn't otherwise understand.
+fn merge_unread(
+    base: &Node,
+    ours: &Node,
+    theirs: &Node,
+    screen: Option<&str>,
+    conflicts: &mut Vec<Conflict>,
+) -> Node {
+    let mut node = Node::new(ours.name().to_string());
+
+    *node.properties_mut() = merge_properties(
+        base.properties(),
+        ours.properties(),
+        theirs.properties(),
+        |key, o, t| {
+            conflicts.push(Conflict::Setting {
+                screen: screen.map(str::to_string),
+                key,
+                ours: o,
+                theirs: t,
+            })
+        },
+    );
+
+    let has_unique_names = |node: &Node| {
+        let children = node.children();
+        children
+            .iter()
+            .enumerate()
+            .all(|(i, x)| children[..i].iter().all(|y| y.name() != x.name()))
+    };
+    if !(has_unique_names(base) && has_unique_names(ours) && has_unique_names(theirs)) {
+        // Without names to match children by, they can only be merged as a whole.
+        *node.children_mut() = match three_way(base.children(), ours.children(), theirs.children())
+        {
+            Some(x) => x.to_vec(),
+            None => {
+                conflicts.push(Conflict::Unmodelled {
+                    screen: screen.map(str::to_string),
+                    node: ours.name().to_string(),
+                });
+                ours.children().to_vec()
+            }
+        };
+        return node;
+    }
+
+    let find = |node: &Node, name: &str| -> Option<Node> {
+        node.children().iter().find(|x| x.name() == name).cloned()
+    };
+    let mut names: Vec<&str> = ours.children().iter().map(Node::name).collect();
+    for child in theirs.children().iter().chain(base.children().iter()) {
+        if !names.contains(&child.name()) {
+            names.push(child.name());
+        }
+    }
+
+    for name in names.into_iter() {
+        let (b, o, t) = (find(base, name), find(ours, name), find(theirs, name));
+        let merged = match (&o, &t) {
+            // The map's settings are merged property by property.
+            (Some(o), Some(t)) if screen.is_none() && name == "meta" => {
+                let empty = Node::new("meta".into());
+                Some(merge_unread(
+                    b.as_ref().unwrap_or(&empty),
+                    o,
+                    t,
+                    None,
+                    conflicts,
+                ))
+            }
+            _ => match three_way(&b, &o, &t) {
+                Some(x) => x.clone(),
+                None => {
+                    conflicts.push(Conflict::Unmodelled {
+                        screen: screen.map(str::to_string),
+                        node: name.to_string(),
+                    });
+                    o
+                }
+            },
+        };
+        if let Some(child) = merged {
+            node.push_child(child);
+        }
+    }
+
+    node
+}
+
+fn merge_screens(
+    base: &[Screen],
+    ours: &[Screen],
+    theirs: &[Screen],
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Screen> {
+    let by_name = |x: &[Screen]| -> HashMap<String, Screen> {
+        x.iter()
+            .map(|x| (x.name().to_string(), x.clone()))
+            .collect()
+    };
+    let (base_map, ours_map, theirs_map) = (by_name(base), by_name(ours), by_name(theirs));
+
+    let mut names: Vec<&str> = ours.iter().map(Screen::name).collect();
+    for screen in theirs.iter() {
+        if !names.contains(&screen.name()) {
+            names.push(screen.name());
+        }
+    }
+
+    let mut result = Vec::new();
+    for name in names.into_iter() {
+        let (b, o, t) = (base_map.get(name), ours_map.get(name), theirs_map.get(name));
+        let screen = name.to_string();
+        match (b, o, t) {
+            (Some(b), Some(o), Some(t)) => result.push(merge_screen(b, o, t, conflicts)),
+            (None, Some(o), Some(t)) => {
+                if o != t {
+                    conflicts.push(Conflict::ScreenAdded { screen });
+                }
+                result.push(o.clone());
+            }
+            (None, Some(x), None) | (None, None, Some(x)) => result.push(x.clone()),
+            (Some(b), Some(o), None) => {
+                if b != o {
+                    conflicts.push(Conflict::ScreenDeleted {
+                        screen,
+                        deleted_by: Side::Theirs,
+                    });
+                    result.push(o.clone());
+                }
+            }
+            (Some(b), None, Some(t)) => {
+                if b != t {
+                    conflicts.push(Conflict::ScreenDeleted {
+                        screen,
+                        deleted_by: Side::Ours,
+                    });
+                }
+            }
+            (_, None, None) => {}
+        }
+    }
+    result
+}
+
+fn merge_screen(
+    base: &Screen,
+    ours: &Screen,
+    theirs: &Screen,
+    conflicts: &mut Vec<Conflict>,
+) -> Screen {
+    let name = ours.name().to_string();
+    let mut screen = ours.clone();
+
+    let (b, o, t) = (base.shape(), ours.shape(), theirs.shape());
+    let position = match three_way(&b.position(), &o.position(), &t.position()) {
+        Some(x) => *x,
+        None => {
+            conflicts.push(Conflict::ScreenMoved {
+                screen: name.clone(),
+                ours: o.position(),
+                theirs: t.position(),
+            });
+            o.position()
+        }
+    };
+    let size = match three_way(&b.size(), &o.size(), &t.size()) {
+        Some(x) => *x,
+        None => {
+            conflicts.push(Conflict::ScreenResized {
+                screen: name.clone(),
+                ours: o.size(),
+                theirs: t.size(),
+            });
+            o.size()
+        }
+    };
+    *screen.shape_mut() = Rect::new(position, size);
+
+    screen.unread = merge_unread(
+        &base.unread,
+        &ours.unread,
+        &theirs.unread,
+        Some(&name),
+        conflicts,
+    );
+
+    // Tiles follow the screen when it moves, but stay put in the world when it's resized.
+    let (ours_shift, theirs_shift) = (tile_shift(&b, &o), tile_shift(&b, &t));
+    let shift = if o.position() == b.position() {
+        theirs_shift
+    } else {
+        ours_shift
+    };
+    for &layer in [TileLayer::Solids, TileLayer::Bg].iter() {
+        let (ours_shift, theirs_shift, shift) = match (ours_shift, theirs_shift, shift) {
+            (Some(o), Some(t), Some(x)) => (o, t, x),
+            _ => {
+                conflicts.push(Conflict::TilesUnaligned {
+                    screen: name.clone(),
+                    layer,
+                });
+                continue;
+            }
+        };
+        let (tiles, cells) = merge_tiles(
+            layer.get(base),
+            (layer.get(ours), ours_shift),
+            (layer.get(theirs), theirs_shift),
+            shift,
+        );
+        *layer.get_mut(&mut screen) = tiles;
+        if !cells.is_empty() {
+            conflicts.push(Conflict::Tiles {
+                screen: name.clone(),
+                layer,
+                cells,
+            });
+        }
+    }
+
+    *screen.entities_mut() = merge_entities(
+        &name,
+        EntityKind::Entity,
+        base.entities(),
+        ours.entities(),
+        theirs.entities(),
+        conflicts,
+    );
+    *screen.triggers_mut() = merge_entities(
+        &name,
+        EntityKind::Trigger,
+        base.triggers(),
+        ours.triggers(),
+        theirs.triggers(),
+        conflicts,
+    );
+
+    screen
+}
+
+/// How far a side's tiles are from the base's, in tiles, or `None` if they're off by part of a
+/// tile. A moved screen takes its tiles along, while a resized one leaves them in place, so
+/// resizing from the left or top shifts them.
+fn tile_shift(base: &IntRect, side: &IntRect) -> Option<Point<i32, ScreenTiles>> {
+    if side.size() == base.size() {
+        return Some(Point::new(0, 0));
+    }
+    let tiles = |side: i32, base: i32| {
+        let pixels = side as i64 - base as i64;
+        if pixels % TILE_SIZE as i64 == 0 {
+            Some((pixels / TILE_SIZE as i64) as i32)
+        } else {
+            None
+        }
+    };
+    Some(Point::new(
+        tiles(side.left(), base.left())?,
+        tiles(side.top(), base.top())?,
+    ))
+}
+
+/// Merges cell by cell, sized to whichever side was resized. Each side's tiles come with their
+/// shift from the base's, and `shift` is the merged screen's.
+fn merge_tiles(
+    base: &Tiles,
+    (ours, ours_shift): (&Tiles, Point<i32, ScreenTiles>),
+    (theirs, theirs_shift): (&Tiles, Point<i32, ScreenTiles>),
+    shift: Point<i32, ScreenTiles>,
+) -> (Tiles, Vec<Point<i32, ScreenTiles>>) {
+    let width = *three_way(&base.width(), &ours.width(), &theirs.width()).unwrap_or(&ours.width());
+    let height =
+        *three_way(&base.height(), &ours.height(), &theirs.height()).unwrap_or(&ours.height());
+    // The cell of `tiles` shifted by `by` matching the merged cell at `x` and `y`.
+    let get = |tiles: &Tiles, by: Point<i32, ScreenTiles>, x: u32, y: u32| {
+        let x = x as i64 + shift.x() as i64 - by.x() as i64;
+        let y = y as i64 + shift.y() as i64 - by.y() as i64;
+        if x < 0 || y < 0 || x > u32::MAX as i64 || y > u32::MAX as i64 {
+            return None;
+        }
+        tiles.get(x as u32, y as u32)
+    };
+
+    let mut tiles = Tiles::new(width, height);
+    let mut cells = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let b = get(base, Point::new(0, 0), x, y);
+            let (o, t) = (get(ours, ours_shift, x, y), get(theirs, theirs_shift, x, y));
+            let tile = match three_way(&b, &o, &t) {
+                Some(x) => *x,
+                None => {
+                    cells.push(Point::new(x as i32, y as i32));
+                    o
+                }
+            };
+            tiles.set(x, y, tile.unwrap_or(AIR));
+        }
+    }
+    (tiles, cells)
+}
+
+fn merge_entities(
+    screen: &str,
+    kind: EntityKind,
+    base: &[Entity],
+    ours: &[Entity],
+    theirs: &[Entity],
+    conflicts: &mut Vec<Conflict>,
+) -> Vec<Entity> {
+    let by_id =
+        |x: &[Entity]| -> HashMap<i32, Entity> { x.iter().map(|x| (x.id(), x.clone())).collect() };
+    let (base_map, ours_map, theirs_map) = (by_id(base), by_id(ours), by_id(theirs));
+
+    let mut ids: Vec<i32> = ours.iter().map(Entity::id).collect();
+    for entity in theirs.iter() {
+        if !ids.contains(&entity.id()) {
+            ids.push(entity.id());
+        }
+    }
+
+    let mut result = Vec::new();
+    for id in ids.into_iter() {
+        let (b, o, t) = (base_map.get(&id), ours_map.get(&id), theirs_map.get(&id));
+        let screen = screen.to_string();
+        match (b, o, t) {
+            (Some(b), Some(o), Some(t)) => {
+                result.push(merge_entity(&screen, kind, b, o, t, conflicts))
+            }
+            (None, Some(o), Some(t)) => {
+                if o != t {
+                    conflicts.push(Conflict::EntityAdded { screen, kind, id });
+                }
+                result.push(o.clone());
+            }
+            (None, Some(x), None) | (None, None, Some(x)) => result.push(x.clone()),
+            (Some(b), Some(o), None) => {
+                if b != o {
+                    conflicts.push(Conflict::EntityDeleted {
+                        screen,
+                        kind,
+                        id,
+                        deleted_by: Side::Theirs,
+                    });
+                    result.push(o.clone());
+                }
+            }
+            (Some(b), None, Some(t)) => {
+                if b != t {
+                    conflicts.push(Conflict::EntityDeleted {
+                        screen,
+                        kind,
+                        id,
+                        deleted_by: Side::Ours,
+                    });
+                }
+            }
+            (_, None, None) => {}
+        }
+    }
+    result
+}
+
+fn merge_entity(
+    screen: &str,
+    kind: EntityKind,
+    base: &Entity,
+    ours: &Entity,
+    theirs: &Entity,
+    conflicts: &mut Vec<Conflict>,
+) -> Entity {
+    let id = ours.id();
+    let mut entity = ours.clone();
+
+    match three_way(&base.name(), &ours.name(), &theirs.name()) {
+        Some(x) => *entity.name_mut() = x.to_string(),
+        None => conflicts.push(Conflict::EntityRenamed {
+            screen: screen.to_string(),
+            kind,
+            id,
+            ours: ours.name().to_string(),
+            theirs: theirs.name().to_string(),
+        }),
+    }
+
+    match three_way(&base.position(), &ours.position(), &theirs.position()) {
+        Some(x) => *entity.position_mut() = *x,
+        None => conflicts.push(Conflict::EntityMoved {
+            screen: screen.to_string(),
+            kind,
+            id,
+            ours: ours.position(),
+            theirs: theirs.position(),
+        }),
+    }
+
+    // Nodes carry their other properties along, so they're merged together.
+    let nodes = |x: &Entity| (x.nodes().to_vec(), x.unread_nodes.clone());
+    match three_way(&nodes(base), &nodes(ours), &nodes(theirs)) {
+        Some((positions, unread)) => {
+            *entity.nodes_mut() = positions.clone();
+            entity.unread_nodes = unread.clone();
+        }
+        None => conflicts.push(Conflict::EntityNodes {
+            screen: screen.to_string(),
+            kind,
+            id,
+            ours: ours.nodes().to_vec(),
+            theirs: theirs.nodes().to_vec(),
+        }),
+    }
+
+    let mut unread_conflicts = Vec::new();
+    entity.unread = merge_unread(
+        &base.unread,
+        &ours.unread,
+        &theirs.unread,
+        Some(screen),
+        &mut unread_conflicts,
+    );
+    conflicts.extend(unread_conflicts.into_iter().map(|x| match x {
+        Conflict::Setting {
+            key, ours, theirs, ..
+        } => Conflict::EntityProperty {
+            screen: screen.to_string(),
+            kind,
+            id,
+            key,
+            ours,
+            theirs,
+        },
+        x => x,
+    }));
+
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::{lint, Lint};
+
+    fn entity(name: &str, id: i32, x: i32) -> Entity {
+        Entity::new(name.to_string(), id, Point::new(x, 8))
+    }
+
+    fn screen(name: &str, x: i32, entities: Vec<Entity>) -> Screen {
+        let mut screen = Screen::new(name.to_string(), Rect::from_edges(x, 0, x + 320, 184));
+        *screen.entities_mut() = entities;
+        screen
+    }
+
+    fn map(screens: Vec<Screen>) -> CelesteMap {
+        let mut map = CelesteMap::new("test".to_string());
+        *map.screens_mut() = screens;
+        map
+    }
+
+    fn entities(map: &CelesteMap, screen: usize) -> Vec<(String, i32)> {
+        map.screens()[screen]
+            .entities()
+            .iter()
+            .map(|x| (x.name().to_string(), x.id()))
+            .collect()
+    }
+
+    #[test]
+    fn concurrent_adds_in_a_screen_are_both_kept() {
+        let base = map(vec![screen("a", 0, vec![entity("player", 0, 8)])]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0]
+            .entities_mut()
+            .push(entity("strawberry", 1, 40));
+        let mut theirs = base.clone();
+        theirs.screens_mut()[0]
+            .entities_mut()
+            .push(entity("spring", 1, 80));
+
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean(), "{:?}", result.conflicts());
+        assert_eq!(
+            entities(result.map(), 0),
+            vec![
+                ("player".to_string(), 0),
+                ("strawberry".to_string(), 1),
+                ("spring".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_adds_are_kept_once() {
+        let base = map(vec![screen("a", 0, vec![entity("player", 0, 8)])]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0]
+            .entities_mut()
+            .push(entity("strawberry", 1, 40));
+
+        let result = merge(&base, &ours, &ours);
+        assert!(result.is_clean());
+        assert_eq!(result.map(), &ours);
+    }
+
+    #[test]
+    fn concurrent_adds_in_different_screens_get_unique_ids() {
+        let base = map(vec![
+            screen("a", 0, vec![entity("player", 0, 8)]),
+            screen("b", 320, vec![entity("player", 1, 8)]),
+        ]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0]
+            .entities_mut()
+            .push(entity("strawberry", 2, 40));
+        let mut theirs = base.clone();
+        theirs.screens_mut()[1]
+            .entities_mut()
+            .push(entity("strawberry", 2, 40));
+
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(entities(result.map(), 1)[1], ("strawberry".to_string(), 3));
+        assert!(!lint(result.map())
+            .iter()
+            .any(|x| matches!(x.lint(), Lint::DuplicateEntityId { .. })));
+    }
+
+    #[test]
+    fn renumbered_adds_keep_their_references() {
+        let base = map(vec![screen("a", 0, vec![entity("player", 0, 8)])]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0]
+            .entities_mut()
+            .push(entity("strawberry", 1, 40));
+        let mut theirs = base.clone();
+        let mut door = entity("door", 2, 120);
+        door.properties_mut()
+            .push(("key".to_string(), Value::Int(1)));
+        theirs.screens_mut()[0]
+            .entities_mut()
+            .extend(vec![entity("key", 1, 80), door]);
+
+        let result = merge_with_references(&base, &ours, &theirs, &["key"]);
+        assert!(result.is_clean());
+        let merged = &result.map().screens()[0].entities();
+        assert_eq!(merged[2].name(), "key");
+        let key = merged[2].id();
+        assert_ne!(key, 1);
+        assert_eq!(
+            merged[3].properties(),
+            &[("key".to_string(), Value::Int(key))]
+        );
+    }
+
+    #[test]
+    fn delete_against_modify_conflicts() {
+        let base = map(vec![screen(
+            "a",
+            0,
+            vec![entity("player", 0, 8), entity("spring", 1, 40)],
+        )]);
+        let mut deleted = base.clone();
+        deleted.screens_mut()[0].entities_mut().pop();
+        let mut moved = base.clone();
+        *moved.screens_mut()[0].entities_mut()[1].position_mut() = Point::new(60, 8);
+
+        let result = merge(&base, &deleted, &moved);
+        assert_eq!(
+            result.conflicts(),
+            &[Conflict::EntityDeleted {
+                screen: "a".to_string(),
+                kind: EntityKind::Entity,
+                id: 1,
+                deleted_by: Side::Ours,
+            }]
+        );
+        assert_eq!(entities(result.map(), 0).len(), 1);
+
+        let result = merge(&base, &moved, &deleted);
+        assert!(matches!(
+            result.conflicts(),
+            [Conflict::EntityDeleted {
+                deleted_by: Side::Theirs,
+                ..
+            }]
+        ));
+        assert_eq!(entities(result.map(), 0).len(), 2);
+
+        // Deleting an entity nobody else touched is clean.
+        let result = merge(&base, &base, &deleted);
+        assert!(result.is_clean());
+        assert_eq!(entities(result.map(), 0).len(), 1);
+    }
+
+    #[test]
+    fn tiles_line_up_after_resizing_from_the_left() {
+        let mut base = map(vec![screen("a", 0, vec![])]);
+        base.screens_mut()[0].solids_mut().set(5, 2, '1');
+        let mut ours = base.clone();
+        ours.screens_mut()[0].solids_mut().set(10, 3, '2');
+        let mut theirs = base.clone();
+        theirs.screens_mut()[0].resize(Rect::from_edges(-16, 0, 320, 184));
+
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean(), "{:?}", result.conflicts());
+        let screen = &result.map().screens()[0];
+        assert_eq!(screen.shape(), Rect::from_edges(-16, 0, 320, 184));
+        assert_eq!(screen.solids().width(), 42);
+        assert_eq!(screen.solids().get(7, 2), Some('1'));
+        assert_eq!(screen.solids().get(12, 3), Some('2'));
+        assert_eq!(screen.solids().get(10, 3), Some(AIR));
+    }
+
+    #[test]
+    fn tiles_follow_a_moved_screen() {
+        let base = map(vec![screen("a", 0, vec![])]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0].solids_mut().set(10, 3, '2');
+        let mut theirs = base.clone();
+        *theirs.screens_mut()[0].shape_mut() = Rect::from_edges(800, 0, 1120, 184);
+
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        let screen = &result.map().screens()[0];
+        assert_eq!(screen.shape().left(), 800);
+        assert_eq!(screen.solids().get(10, 3), Some('2'));
+    }
+
+    #[test]
+    fn resizing_by_part_of_a_tile_conflicts() {
+        let base = map(vec![screen("a", 0, vec![])]);
+        let mut ours = base.clone();
+        ours.screens_mut()[0].solids_mut().set(10, 3, '2');
+        let mut theirs = base.clone();
+        theirs.screens_mut()[0].resize(Rect::from_edges(-4, 0, 320, 184));
+
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.conflicts().contains(&Conflict::TilesUnaligned {
+            screen: "a".to_string(),
+            layer: TileLayer::Solids,
+        }));
+        assert_eq!(
+            result.map().screens()[0].solids(),
+            ours.screens()[0].solids()
+        );
+    }
+}
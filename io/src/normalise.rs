@@ -0,0 +1,112 @@
+//! Canonicalisation of maps, so that equivalent maps are written identically.
+//!
+//! String encodings need no pass of their own, as the writer always picks the smallest of the
+//! lookup, plain and run length encodings for each value.
+
+use std::collections::HashMap;
+
+use crate::{
+    internal::{Node, Value},
+    CelesteMap, Entity,
+};
+
+/// Default property values, by node name.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    defaults: HashMap<String, HashMap<String, Value>>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Schema::default()
+    }
+
+    pub fn insert_default(&mut self, node: String, key: String, value: Value) {
+        self.defaults.entry(node).or_default().insert(key, value);
+    }
+
+    pub fn default_for(&self, node: &str, key: &str) -> Option<&Value> {
+        self.defaults.get(node)?.get(key)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NormaliseOptions<'a> {
+    /// Sorts properties by key.
+    pub sort_properties: bool,
+    /// Removes trailing whitespace from the rows of `solids` and `bg` tile strings.
+    pub trim_tiles: bool,
+    /// Removes properties equal to their default in this schema.
+    pub drop_defaults: Option<&'a Schema>,
+}
+
+impl NormaliseOptions<'_> {
+    /// Options that only make changes the game can't observe.
+    pub fn canonical() -> Self {
+        NormaliseOptions {
+            sort_properties: true,
+            trim_tiles: true,
+            drop_defaults: None,
+        }
+    }
+}
+
+fn normalise_properties(
+    node: &str,
+    properties: &mut Vec<(String, Value)>,
+    options: &NormaliseOptions<'_>,
+) {
+    if let Some(schema) = options.drop_defaults {
+        properties.retain(|(key, value)| schema.default_for(node, key) != Some(value));
+    }
+    if options.sort_properties {
+        properties.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+}
+
+fn trim_tiles(text: &str) -> String {
+    let rows: Vec<&str> = text.lines().map(str::trim_end).collect();
+    rows.join("\n").trim_end().to_string()
+}
+
+/// Normalises `node` and all of its descendants.
+pub fn normalise_node(node: &mut Node, options: &NormaliseOptions<'_>) {
+    let name = node.name().to_string();
+    if options.trim_tiles && (name == "solids" || name == "bg") {
+        for (key, value) in node.properties_mut().iter_mut() {
+            if let (Value::String(x), "innerText") = (&mut *value, key.as_str()) {
+                *x = trim_tiles(x);
+            }
+        }
+    }
+    normalise_properties(&name, node.properties_mut(), options);
+    for child in node.children_mut().iter_mut() {
+        normalise_node(child, options);
+    }
+}
+
+fn normalise_entities(entities: &mut [Entity], options: &NormaliseOptions<'_>) {
+    for entity in entities.iter_mut() {
+        let name = entity.name().to_string();
+        normalise_properties(&name, entity.unread.properties_mut(), options);
+        for child in entity.unread.children_mut().iter_mut() {
+            normalise_node(child, options);
+        }
+    }
+}
+
+/// Normalises the parts of `map` that are kept as they were read.
+///
+/// Properties decoded into fields, such as positions, are always written first, so writing with
+/// [`CelesteMap::write_normalised`] is needed for fully sorted output.
+pub fn normalise_map(map: &mut CelesteMap, options: &NormaliseOptions<'_>) {
+    normalise_node(&mut map.unread, options);
+    for screen in map.screens_mut().iter_mut() {
+        normalise_properties("level", screen.unread.properties_mut(), options);
+        for child in screen.unread.children_mut().iter_mut() {
+            normalise_node(child, options);
+        }
+        normalise_entities(screen.entities_mut(), options);
+        normalise_entities(screen.triggers_mut(), options);
+    }
+}
@@ -1,16 +1,26 @@
 use std::convert::TryFrom;
 
-use fujiformer_geom::{IntRect, Point, Rect, Size};
+use fujiformer_geom::{
+    Grid, IntRect, Point, Rect, Rounding, ScreenPixels, ScreenTiles, Size, TILE_SIZE,
+};
 use log::warn;
 use thiserror::Error;
 
-use crate::{internal::Node, CelesteMap};
+use crate::{
+    entity::{decode_entities, encode_entities, EntitiesDecodeError},
+    internal::{Node, Value},
+    CelesteMap, Entity, Tiles,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Screen {
     name: String,
     rect: IntRect,
-    unread: Node,
+    entities: Vec<Entity>,
+    triggers: Vec<Entity>,
+    solids: Tiles,
+    bg: Tiles,
+    pub(crate) unread: Node,
 }
 
 impl Screen {
@@ -18,10 +28,24 @@ impl Screen {
         Screen {
             name,
             rect,
+            entities: Vec::new(),
+            triggers: Vec::new(),
+            solids: Tiles::new(
+                tile_count(rect.size().width()),
+                tile_count(rect.size().height()),
+            ),
+            bg: Tiles::new(
+                tile_count(rect.size().width()),
+                tile_count(rect.size().height()),
+            ),
             unread: Node::new("level".into()),
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn shape(&self) -> IntRect {
         self.rect
     }
@@ -29,6 +53,187 @@ impl Screen {
     pub fn shape_mut(&mut self) -> &mut IntRect {
         &mut self.rect
     }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn entities_mut(&mut self) -> &mut Vec<Entity> {
+        &mut self.entities
+    }
+
+    pub fn triggers(&self) -> &[Entity] {
+        &self.triggers
+    }
+
+    pub fn triggers_mut(&mut self) -> &mut Vec<Entity> {
+        &mut self.triggers
+    }
+
+    /// Foreground tiles.
+    pub fn solids(&self) -> &Tiles {
+        &self.solids
+    }
+
+    pub fn solids_mut(&mut self) -> &mut Tiles {
+        &mut self.solids
+    }
+
+    /// Background tiles.
+    pub fn bg(&self) -> &Tiles {
+        &self.bg
+    }
+
+    pub fn bg_mut(&mut self) -> &mut Tiles {
+        &mut self.bg
+    }
+
+    /// Moves the screen's edges to `rect`, keeping its contents where they are in the world.
+    ///
+    /// Tile layers, including object tiles, are cropped or padded with empty cells, and entities,
+    /// triggers, their nodes and decals are shifted by however far the top left corner moved.
+    /// Returns the ids of entities and triggers that end up outside the screen.
+    pub fn resize(&mut self, rect: IntRect) -> Vec<i32> {
+        let moved = rect.position() - self.rect.position();
+        let tiles_moved = moved.to_tiles(Rounding::Floor);
+        let at = Point::new(-tiles_moved.x(), -tiles_moved.y());
+        let (width, height) = (
+            tile_count(rect.size().width()),
+            tile_count(rect.size().height()),
+        );
+        for layer in [&mut self.solids, &mut self.bg].iter_mut() {
+            let mut resized = Tiles::new(width, height);
+            resized.grid_mut().paste(layer.grid(), at);
+            **layer = resized;
+        }
+        for child in self.unread.children_mut().iter_mut() {
+            if child.name() == "objtiles" {
+                resize_object_tiles(child, width, height, at);
+            }
+        }
+
+        let shift = Point::new(-moved.x(), -moved.y());
+        let bounds = Rect::new(Point::new(0, 0), rect.size().to_screen());
+        let mut outside = Vec::new();
+        for entity in self.entities.iter_mut().chain(self.triggers.iter_mut()) {
+            *entity.position_mut() += shift;
+            for node in entity.nodes_mut().iter_mut() {
+                *node += shift;
+            }
+            if !bounds.contains(entity.position()) {
+                outside.push(entity.id());
+            }
+        }
+        for child in self.unread.children_mut().iter_mut() {
+            if child.name() == "fgdecals" || child.name() == "bgdecals" {
+                for decal in child.children_mut().iter_mut() {
+                    shift_decal(decal, shift);
+                }
+            }
+        }
+        self.rect = rect;
+        outside
+    }
+
+    /// Settings such as music and lighting that aren't otherwise decoded.
+    pub fn properties(&self) -> &[(String, Value)] {
+        self.unread.properties()
+    }
+
+    pub fn properties_mut(&mut self) -> &mut Vec<(String, Value)> {
+        self.unread.properties_mut()
+    }
+}
+
+/// Number of tiles needed to cover `pixels`.
+fn tile_count(pixels: u32) -> u32 {
+    Rounding::Ceil.divide_unsigned(pixels, TILE_SIZE)
+}
+
+/// The cell object tiles use for no tile.
+const NO_OBJECT_TILE: i32 = -1;
+
+/// Crops or pads an `objtiles` node, whose text is newline separated rows of comma separated tile
+/// indices, to `width` by `height` with its old top left cell at `at`.
+fn resize_object_tiles(node: &mut Node, width: u32, height: u32, at: Point<i32, ScreenTiles>) {
+    let text = match node.properties().iter().find(|(key, _)| key == "innerText") {
+        Some((_, Value::String(x))) => x.clone(),
+        _ => return,
+    };
+    let rows: Vec<Vec<i32>> = text
+        .lines()
+        .map(|row| {
+            row.split(',')
+                .map(|x| x.trim().parse().unwrap_or(NO_OBJECT_TILE))
+                .collect()
+        })
+        .collect();
+    let old_width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut old = Grid::new(
+        Size::new(old_width as u32, rows.len() as u32),
+        NO_OBJECT_TILE,
+    );
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &tile) in row.iter().enumerate() {
+            old.set(Point::new(x as i32, y as i32), tile);
+        }
+    }
+    let mut resized = Grid::new(Size::new(width, height), NO_OBJECT_TILE);
+    resized.paste(&old, at);
+
+    // Written like the game does, without trailing empty cells or rows.
+    let mut rows: Vec<String> = resized
+        .rows()
+        .map(|row| {
+            let end = row
+                .iter()
+                .rposition(|&x| x != NO_OBJECT_TILE)
+                .map_or(0, |x| x + 1);
+            let cells: Vec<String> = row[..end].iter().map(i32::to_string).collect();
+            cells.join(",")
+        })
+        .collect();
+    while matches!(rows.last(), Some(x) if x.is_empty()) {
+        rows.pop();
+    }
+    for (key, value) in node.properties_mut().iter_mut() {
+        if key == "innerText" {
+            *value = rows.join("\n").into();
+        }
+    }
+}
+
+/// Moves a decal by `shift`, whether its position is stored as integers or floats.
+fn shift_decal(decal: &mut Node, shift: Point<i32, ScreenPixels>) {
+    for (key, value) in decal.properties_mut().iter_mut() {
+        let by = match key.as_str() {
+            "x" => shift.x(),
+            "y" => shift.y(),
+            _ => continue,
+        };
+        match value {
+            Value::Int(x) => *x += by,
+            Value::Float(x) => *x += by as f32,
+            _ => {}
+        }
+    }
+}
+
+fn decode_tiles(node: Option<Node>, width: u32, height: u32) -> Result<Tiles, ScreensDecodeError> {
+    let text = match node {
+        Some(node) => match node.properties().iter().find(|(key, _)| key == "innerText") {
+            Some((_, value)) => {
+                String::try_from(value.clone()).map_err(|_| ScreensDecodeError::TilesNotString)?
+            }
+            None => String::new(),
+        },
+        None => String::new(),
+    };
+    Ok(Tiles::from_text(
+        &text,
+        tile_count(width),
+        tile_count(height),
+    ))
 }
 
 #[derive(Error, Debug)]
@@ -55,6 +260,10 @@ pub enum ScreensDecodeError {
     MissingHeight,
     #[error("level height not int")]
     HeightNotInt,
+    #[error("level tiles not string")]
+    TilesNotString,
+    #[error("failed decoding entities")]
+    EntitiesDecodeError(#[from] EntitiesDecodeError),
 }
 
 pub fn decode_screens(map: &mut CelesteMap) -> Result<(), ScreensDecodeError> {
@@ -97,9 +306,23 @@ pub fn decode_screens(map: &mut CelesteMap) -> Result<(), ScreensDecodeError> {
             width.ok_or(ScreensDecodeError::MissingWidth)??,
             height.ok_or(ScreensDecodeError::MissingHeight)??,
         );
+        let entities = match child.take_child_with_name("entities") {
+            Some(mut node) => decode_entities(&mut node)?,
+            None => Vec::new(),
+        };
+        let triggers = match child.take_child_with_name("triggers") {
+            Some(mut node) => decode_entities(&mut node)?,
+            None => Vec::new(),
+        };
+        let solids = decode_tiles(child.take_child_with_name("solids"), width, height)?;
+        let bg = decode_tiles(child.take_child_with_name("bg"), width, height)?;
         map.screens_mut().push({
             let mut screen =
                 Screen::new(name, Rect::new(Point::new(x, y), Size::new(width, height)));
+            screen.entities = entities;
+            screen.triggers = triggers;
+            screen.solids = solids;
+            screen.bg = bg;
             screen.unread = child;
             screen
         });
@@ -107,3 +330,34 @@ pub fn decode_screens(map: &mut CelesteMap) -> Result<(), ScreensDecodeError> {
 
     Ok(())
 }
+
+fn encode_tiles(name: &str, tiles: &Tiles) -> Node {
+    let mut node = Node::new(name.into());
+    node.push_property("innerText".into(), tiles.to_text().into());
+    node
+}
+
+pub fn encode_screens(map: &CelesteMap) -> Node {
+    let mut node = Node::new("levels".into());
+    for screen in map.screens().iter() {
+        let (pos, size) = (screen.rect.position(), screen.rect.size());
+        let mut child = Node::new("level".into());
+        child.push_property("name".into(), screen.name.clone().into());
+        child.push_property("x".into(), pos.x().into());
+        child.push_property("y".into(), pos.y().into());
+        child.push_property("width".into(), (size.width() as i32).into());
+        child.push_property("height".into(), (size.height() as i32).into());
+        for (key, value) in screen.unread.properties().iter() {
+            child.push_property(key.clone(), value.clone());
+        }
+        child.push_child(encode_entities("entities", &screen.entities));
+        child.push_child(encode_entities("triggers", &screen.triggers));
+        child.push_child(encode_tiles("solids", &screen.solids));
+        child.push_child(encode_tiles("bg", &screen.bg));
+        for grandchild in screen.unread.children().iter() {
+            child.push_child(grandchild.clone());
+        }
+        node.push_child(child);
+    }
+    node
+}
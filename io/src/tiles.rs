@@ -0,0 +1,72 @@
+use fujiformer_geom::{Grid, Point, Size};
+
+/// The tile used for empty cells.
+pub const AIR: char = '0';
+
+/// A grid of tileset characters, as stored in a screen's `solids` and `bg` nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tiles {
+    grid: Grid<char>,
+}
+
+impl Tiles {
+    pub fn new(width: u32, height: u32) -> Self {
+        Tiles {
+            grid: Grid::new(Size::new(width, height), AIR),
+        }
+    }
+
+    /// Parses newline separated rows, where missing cells are air and cells outside the grid are
+    /// dropped.
+    pub fn from_text(text: &str, width: u32, height: u32) -> Self {
+        let mut tiles = Tiles::new(width, height);
+        for (y, row) in text.lines().enumerate().take(height as usize) {
+            for (x, tile) in row.chars().enumerate().take(width as usize) {
+                tiles.set(x as u32, y as u32, tile);
+            }
+        }
+        tiles
+    }
+
+    /// Formats the grid as rows with trailing air removed, as the game writes it.
+    pub fn to_text(&self) -> String {
+        let mut rows: Vec<String> = self
+            .grid
+            .rows()
+            .map(|row| {
+                let row: String = row.iter().collect();
+                row.trim_end_matches(AIR).to_string()
+            })
+            .collect();
+        while matches!(rows.last(), Some(x) if x.is_empty()) {
+            rows.pop();
+        }
+        rows.join("\n")
+    }
+
+    pub fn width(&self) -> u32 {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.grid.height()
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<char> {
+        self.grid.get(Point::new(x as i32, y as i32)).copied()
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, tile: char) {
+        let old = self.grid.set(Point::new(x as i32, y as i32), tile);
+        assert!(old.is_some(), "tile out of bounds");
+    }
+
+    /// The tiles as a grid, for region operations.
+    pub fn grid(&self) -> &Grid<char> {
+        &self.grid
+    }
+
+    pub fn grid_mut(&mut self) -> &mut Grid<char> {
+        &mut self.grid
+    }
+}
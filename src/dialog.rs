@@ -0,0 +1,132 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+
+use crate::{prompt::UiResources, ui::UiEventStage};
+
+pub struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ErrorDialog>()
+            .add_system(dismiss_error.system().label(UiEventStage))
+            .add_system(show_error.system().after(UiEventStage));
+    }
+}
+
+/// An error shown in the middle of the window until dismissed with Escape, Enter or a click.
+#[derive(Default)]
+pub struct ErrorDialog(Option<ShownError>);
+
+struct ShownError {
+    title: String,
+    message: String,
+}
+
+impl ErrorDialog {
+    /// Shows `error` along with every error it was caused by, one per line.
+    pub fn show(&mut self, title: impl Into<String>, error: &dyn Error) {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            message.push_str("\ncaused by: ");
+            message.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        self.0 = Some(ShownError {
+            title: title.into(),
+            message,
+        });
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+fn dismiss_error(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut dialog: ResMut<ErrorDialog>,
+) {
+    if dialog.is_open()
+        && (keys.just_pressed(KeyCode::Escape)
+            || keys.just_pressed(KeyCode::Return)
+            || buttons.just_pressed(MouseButton::Left))
+    {
+        dialog.0 = None;
+    }
+}
+
+struct ErrorDialogUi;
+
+fn show_error(
+    mut commands: Commands,
+    dialog: Res<ErrorDialog>,
+    existing: Query<Entity, With<ErrorDialogUi>>,
+    res: Res<UiResources>,
+) {
+    if !dialog.is_changed() {
+        return;
+    }
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let shown = match dialog.0.as_ref() {
+        Some(x) => x,
+        None => return,
+    };
+
+    let style = |font_size, color| TextStyle {
+        font: res.font.clone(),
+        font_size,
+        color,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(20.0),
+                    bottom: Val::Percent(40.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(60.0), Val::Auto),
+                // Children are laid out bottom up, so reverse them to read top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(16.0)),
+                ..Default::default()
+            },
+            material: res.panel_color.clone(),
+            ..Default::default()
+        })
+        .insert(ErrorDialogUi)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    shown.title.clone(),
+                    style(24.0, Color::rgb(1.0, 0.4, 0.4)),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            for line in shown.message.lines() {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        line.to_string(),
+                        style(18.0, Color::WHITE),
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                });
+            }
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    "Press Escape to dismiss",
+                    style(14.0, Color::GRAY),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
@@ -1,14 +1,20 @@
+mod dialog;
 mod map;
+mod prompt;
 mod ui;
 
 use bevy::prelude::*;
+use dialog::DialogPlugin;
 use map::MapPlugin;
+use prompt::PromptPlugin;
 use ui::FfUiPlugin;
 
 fn main() {
     App::build()
         .add_plugins(DefaultPlugins)
         .add_plugin(FfUiPlugin)
+        .add_plugin(PromptPlugin)
+        .add_plugin(DialogPlugin)
         .add_plugin(MapPlugin)
         .run();
 }
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use fujiformer_io::autofill::{generate_fillers, replace_fillers, AutofillOptions};
+
+use super::{history::History, ui::MapUiResources, Map};
+
+/// Fillers generated from the screen layout, shown over the map until applied or dismissed.
+#[derive(Default)]
+pub struct FillerPreview {
+    pub options: AutofillOptions,
+    shown: bool,
+}
+
+impl FillerPreview {
+    /// Forgets the preview is shown, such as when its sprites are despawned with the map.
+    pub fn hide(&mut self) {
+        self.shown = false;
+    }
+}
+
+pub struct FillerPreviewSprite;
+
+/// Toggles the preview with F, and with Enter replaces the map's fillers with the previewed ones.
+/// The preview is regenerated whenever the map changes while it is shown.
+pub fn filler_preview(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut preview: ResMut<FillerPreview>,
+    mut history: ResMut<History>,
+    mut maps: Query<(&mut Map, ChangeTrackers<Map>)>,
+    previews: Query<Entity, With<FillerPreviewSprite>>,
+    res: Res<MapUiResources>,
+) {
+    let (mut map, changes) = match maps.iter_mut().next() {
+        Some(x) => x,
+        None => return,
+    };
+    let toggle = keys.just_pressed(KeyCode::F);
+    let apply = preview.shown && keys.just_pressed(KeyCode::Return);
+    let stale = preview.shown && changes.is_changed();
+    if !toggle && !apply && !stale {
+        return;
+    }
+
+    for entity in previews.iter() {
+        commands.entity(entity).despawn();
+    }
+    if toggle || apply {
+        preview.shown = toggle && !preview.shown;
+    }
+
+    if apply {
+        // The filler entities are updated to match by the map sync.
+        history.begin("generate fillers");
+        replace_fillers(&mut map.0, &preview.options);
+        history.end();
+        info!("replaced fillers with {} generated", map.0.fillers().len());
+    } else if preview.shown {
+        let generated = generate_fillers(&map.0, &preview.options);
+        debug!("previewing {} generated fillers", generated.len());
+        for filler in generated.iter() {
+            let rect = filler.pixel_shape();
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite::new(rect.sprite_size()),
+                    material: res.filler_preview_color.clone(),
+                    transform: rect.centred_transform(1.0),
+                    ..Default::default()
+                })
+                .insert(FillerPreviewSprite);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use fujiformer_geom::{IntRect, Rect};
+use fujiformer_io::Screen;
+
+use super::{select::Selected, ui::MapCamera};
+
+/// The closest the camera can zoom in, as map pixels per window pixel.
+pub const MIN_SCALE: f32 = 1.0 / 16.0;
+/// The furthest the camera can zoom out, as map pixels per window pixel.
+pub const MAX_SCALE: f32 = 32.0;
+/// The depth the map camera sits at, in front of everything it shows.
+pub const CAMERA_Z: f32 = 999.9;
+
+/// Window pixels per second the keyboard pans by.
+const KEY_PAN_SPEED: f32 = 800.0;
+/// How quickly transitions close in on their target, as the fraction left after a second.
+const TRANSITION_REMAINING_PER_SECOND: f32 = 1e-4;
+/// Space left around what's being fitted, as a fraction of its size.
+const FIT_MARGIN: f32 = 0.1;
+
+/// Where the camera is moving to in a smooth transition, if anywhere.
+#[derive(Default)]
+pub struct CameraTarget(Option<CameraView>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraView {
+    /// The map position in the middle of the window.
+    pub center: Vec2,
+    pub scale: f32,
+}
+
+impl CameraTarget {
+    pub fn set(&mut self, view: CameraView) {
+        self.0 = Some(view);
+    }
+
+    /// Stops any transition, such as when the camera is moved by hand.
+    pub fn cancel(&mut self) {
+        if self.0.is_some() {
+            self.0 = None;
+        }
+    }
+}
+
+/// Sets the camera's zoom, keeping it within the limits and the map's `y` axis pointing down.
+pub fn set_scale(transform: &mut Transform, scale: f32) {
+    let scale = scale.clamp(MIN_SCALE, MAX_SCALE);
+    transform.scale.x = scale;
+    transform.scale.y = -scale;
+}
+
+/// The window position of the cursor, while the camera is being dragged.
+#[derive(Default)]
+pub struct CameraDrag(Option<Vec2>);
+
+impl CameraDrag {
+    /// Whether the camera is being dragged, so the mouse shouldn't also edit the map.
+    pub fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Dragging with the middle button, or the left button while holding space, pans the camera.
+pub fn drag_camera(
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    mut drag: ResMut<CameraDrag>,
+    mut camera: Query<(&mut Transform, &mut CameraTarget), With<MapCamera>>,
+) {
+    let cursor = windows.get_primary().and_then(|x| x.cursor_position());
+    let held = buttons.pressed(MouseButton::Middle)
+        || (buttons.pressed(MouseButton::Left) && keys.pressed(KeyCode::Space));
+    let starting = buttons.just_pressed(MouseButton::Middle)
+        || (buttons.just_pressed(MouseButton::Left) && keys.pressed(KeyCode::Space));
+
+    let (last, cursor) = match (drag.0, cursor) {
+        (Some(last), Some(cursor)) if held => (last, cursor),
+        (None, Some(cursor)) if starting => {
+            drag.0 = Some(cursor);
+            return;
+        }
+        _ => {
+            if drag.0.is_some() {
+                drag.0 = None;
+            }
+            return;
+        }
+    };
+    drag.0 = Some(cursor);
+
+    if let Some((mut transform, mut target)) = camera.iter_mut().next() {
+        // Moving the camera against the cursor keeps the same point of the map under it.
+        let moved = cursor - last;
+        transform.translation.x -= moved.x * transform.scale.x;
+        transform.translation.y -= moved.y * transform.scale.y;
+        target.cancel();
+    }
+}
+
+/// WASD or the arrow keys pan the camera, at the same speed on screen however far it's zoomed.
+pub fn key_pan_camera(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut camera: Query<(&mut Transform, &mut CameraTarget), With<MapCamera>>,
+) {
+    // Leave Ctrl+S and the like alone.
+    if keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl) {
+        return;
+    }
+    let held = |a, b| keys.pressed(a) || keys.pressed(b);
+    let mut direction = Vec2::ZERO;
+    if held(KeyCode::A, KeyCode::Left) {
+        direction.x -= 1.0;
+    }
+    if held(KeyCode::D, KeyCode::Right) {
+        direction.x += 1.0;
+    }
+    if held(KeyCode::W, KeyCode::Up) {
+        direction.y -= 1.0;
+    }
+    if held(KeyCode::S, KeyCode::Down) {
+        direction.y += 1.0;
+    }
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    if let Some((mut transform, mut target)) = camera.iter_mut().next() {
+        let distance = KEY_PAN_SPEED * time.delta_seconds() * transform.scale.x;
+        transform.translation += (direction.normalize() * distance).extend(0.0);
+        target.cancel();
+    }
+}
+
+/// The view showing all of `rect` in the middle of the window, with a margin around it.
+fn fitted_view(rect: IntRect, window: &Window) -> CameraView {
+    let size = rect.sprite_size() * (1.0 + FIT_MARGIN * 2.0);
+    let scale = (size.x / window.width()).max(size.y / window.height());
+    CameraView {
+        center: rect.centred_transform(0.0).translation.truncate(),
+        scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+    }
+}
+
+/// Home moves the camera to show the whole map, and C to show the selected screens.
+pub fn fit_camera(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    screens: Query<(&Screen, Option<&Selected>)>,
+    mut camera: Query<&mut CameraTarget, With<MapCamera>>,
+) {
+    let selected_only = if keys.just_pressed(KeyCode::Home) {
+        false
+    } else if keys.just_pressed(KeyCode::C) {
+        true
+    } else {
+        return;
+    };
+    let window = match windows.get_primary() {
+        Some(x) => x,
+        None => return,
+    };
+    let bounds = Rect::bounding_box(
+        screens
+            .iter()
+            .filter(|(_, selected)| !selected_only || selected.is_some())
+            .map(|(screen, _)| screen.shape()),
+    );
+    if let (Some(bounds), Some(mut target)) = (bounds, camera.iter_mut().next()) {
+        target.set(fitted_view(bounds, window));
+    }
+}
+
+/// Eases the camera towards its target, at the same pace whatever the frame rate.
+pub fn animate_camera(
+    time: Res<Time>,
+    mut camera: Query<(&mut Transform, &mut CameraTarget), With<MapCamera>>,
+) {
+    for (mut transform, mut target) in camera.iter_mut() {
+        let view = match target.0 {
+            Some(x) => x,
+            None => continue,
+        };
+        let progress = 1.0 - TRANSITION_REMAINING_PER_SECOND.powf(time.delta_seconds());
+        let center = transform.translation.truncate();
+        let scale = transform.scale.x;
+
+        // Zooming in steps of a ratio rather than a difference feels even at every scale.
+        let next_scale = scale * (view.scale / scale).powf(progress);
+        let next_center = center.lerp(view.center, progress);
+        let settled = (next_center - view.center).length() < 0.5 * scale
+            && (next_scale / view.scale - 1.0).abs() < 1e-3;
+        if settled {
+            transform.translation = view.center.extend(CAMERA_Z);
+            set_scale(&mut transform, view.scale);
+            target.0 = None;
+        } else {
+            transform.translation = next_center.extend(CAMERA_Z);
+            set_scale(&mut transform, next_scale);
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Undo and redo for every change to the map.
+//!
+//! Edits aren't recorded by the tools that make them. Instead, whenever the [`Map`] changes it's
+//! compared against a snapshot of how it was after the last recorded edit, and the difference
+//! becomes an edit that can be reverted. Anything that changes the map, directly or through the
+//! synced components, gets undo without doing anything.
+//!
+//! Tools that change the map over several frames, such as dragging, wrap the change in
+//! [`History::begin`] and [`History::end`] so it's undone as one edit. Tools can also do this
+//! for a single frame's change to give the edit a name.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use fujiformer_io::{CelesteMap, Filler, Screen};
+
+use super::Map;
+use crate::prompt::UiResources;
+
+/// How many edits are kept to undo.
+const MAX_UNDO: usize = 200;
+/// How many of the latest edits the history panel lists.
+const PANEL_ROWS: usize = 20;
+
+/// The difference between two versions of the map.
+struct MapEdit {
+    label: String,
+    screens: Option<ScreensEdit>,
+    fillers: Option<(Vec<Filler>, Vec<Filler>)>,
+}
+
+enum ScreensEdit {
+    /// Screens that changed in place, by index, as they were before and after.
+    Changed(BTreeMap<usize, (Screen, Screen)>),
+    /// Screens were added or removed, so the whole list is kept.
+    Replaced(Vec<Screen>, Vec<Screen>),
+}
+
+impl MapEdit {
+    /// The edit turning `before` into `after`, if they differ.
+    fn between(label: String, before: &CelesteMap, after: &CelesteMap) -> Option<MapEdit> {
+        let screens = if before.screens().len() == after.screens().len() {
+            let changed: BTreeMap<usize, (Screen, Screen)> = before
+                .screens()
+                .iter()
+                .zip(after.screens().iter())
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(i, (a, b))| (i, (a.clone(), b.clone())))
+                .collect();
+            if changed.is_empty() {
+                None
+            } else {
+                Some(ScreensEdit::Changed(changed))
+            }
+        } else {
+            Some(ScreensEdit::Replaced(
+                before.screens().to_vec(),
+                after.screens().to_vec(),
+            ))
+        };
+        let fillers =
+            Some((before.fillers().to_vec(), after.fillers().to_vec())).filter(|(a, b)| a != b);
+
+        if screens.is_none() && fillers.is_none() {
+            None
+        } else {
+            Some(MapEdit {
+                label,
+                screens,
+                fillers,
+            })
+        }
+    }
+
+    /// Sets `map` to how it was before the edit, or after it if `redo`.
+    fn apply(&self, map: &mut CelesteMap, redo: bool) {
+        match &self.screens {
+            Some(ScreensEdit::Changed(changed)) => {
+                for (&i, (before, after)) in changed.iter() {
+                    if let Some(screen) = map.screens_mut().get_mut(i) {
+                        *screen = if redo { after } else { before }.clone();
+                    }
+                }
+            }
+            Some(ScreensEdit::Replaced(before, after)) => {
+                *map.screens_mut() = if redo { after } else { before }.clone();
+            }
+            None => {}
+        }
+        if let Some((before, after)) = &self.fillers {
+            *map.fillers_mut() = if redo { after } else { before }.clone();
+        }
+    }
+}
+
+/// Edits that can be undone and redone, most recent last.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<MapEdit>,
+    redo: Vec<MapEdit>,
+    /// The map as of the last recorded edit.
+    snapshot: Option<CelesteMap>,
+    /// Whether the map has changed since the snapshot.
+    dirty: bool,
+    group: Option<Group>,
+    panel_shown: bool,
+}
+
+struct Group {
+    label: String,
+    open: bool,
+}
+
+impl History {
+    /// Starts an edit that lasts until [`History::end`], with every change in between undone
+    /// together.
+    pub fn begin(&mut self, label: impl Into<String>) {
+        self.group = Some(Group {
+            label: label.into(),
+            open: true,
+        });
+    }
+
+    /// Ends the edit started with [`History::begin`], which is recorded once the frame's changes
+    /// have reached the map.
+    pub fn end(&mut self) {
+        if let Some(group) = self.group.as_mut() {
+            group.open = false;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn undo(&mut self, map: &mut CelesteMap) {
+        if let Some(edit) = self.undo.pop() {
+            edit.apply(map, false);
+            if let Some(snapshot) = self.snapshot.as_mut() {
+                edit.apply(snapshot, false);
+            }
+            info!("undid {}", edit.label);
+            self.redo.push(edit);
+        }
+    }
+
+    fn redo(&mut self, map: &mut CelesteMap) {
+        if let Some(edit) = self.redo.pop() {
+            edit.apply(map, true);
+            if let Some(snapshot) = self.snapshot.as_mut() {
+                edit.apply(snapshot, true);
+            }
+            info!("redid {}", edit.label);
+            self.undo.push(edit);
+        }
+    }
+
+    /// Forgets every edit, such as when another map is opened.
+    fn reset(&mut self, map: &CelesteMap) {
+        self.undo.clear();
+        self.redo.clear();
+        self.snapshot = Some(map.clone());
+        self.dirty = false;
+        self.group = None;
+    }
+
+    /// Whether an edit started with [`History::begin`] hasn't ended yet.
+    fn in_progress(&self) -> bool {
+        matches!(self.group, Some(Group { open: true, .. }))
+    }
+
+    /// Records any change to `map` since the snapshot as an edit.
+    fn record(&mut self, map: &CelesteMap) {
+        let label = self
+            .group
+            .take()
+            .map_or_else(|| "edit".to_string(), |x| x.label);
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        let snapshot = match self.snapshot.as_mut() {
+            Some(x) => x,
+            None => {
+                self.snapshot = Some(map.clone());
+                return;
+            }
+        };
+        if let Some(edit) = MapEdit::between(label, snapshot, map) {
+            // Only what changed is copied into the snapshot, rather than the whole map.
+            edit.apply(snapshot, true);
+            self.undo.push(edit);
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+    }
+}
+
+/// Ctrl+Z undoes, Ctrl+Y or Ctrl+Shift+Z redoes and H shows or hides the history panel.
+pub fn history_shortcuts(
+    keys: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut maps: Query<&mut Map>,
+) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if !ctrl {
+        if keys.just_pressed(KeyCode::H) {
+            history.panel_shown = !history.panel_shown;
+        }
+        return;
+    }
+
+    let undo = keys.just_pressed(KeyCode::Z) && !shift;
+    let redo = keys.just_pressed(KeyCode::Y) || (keys.just_pressed(KeyCode::Z) && shift);
+    if !undo && !redo {
+        return;
+    }
+    // Leave edits in progress alone, as they'd be recorded over the undone state.
+    if history.in_progress() {
+        return;
+    }
+    if let Some(mut map) = maps.iter_mut().next() {
+        if undo && history.can_undo() {
+            history.undo(&mut map.0);
+        } else if redo && history.can_redo() {
+            history.redo(&mut map.0);
+        }
+    }
+}
+
+/// Records the frame's changes to the map, once every tool and the sync have run.
+pub fn record_history(mut history: ResMut<History>, maps: Query<(&Map, ChangeTrackers<Map>)>) {
+    let (map, tracker) = match maps.iter().next() {
+        Some(x) => x,
+        None => return,
+    };
+    if tracker.is_added() {
+        history.reset(&map.0);
+        return;
+    }
+    // Only borrow mutably when there's something to do, so the panel is only redrawn then.
+    if tracker.is_changed() && !history.dirty {
+        history.dirty = true;
+    }
+    if history.in_progress() || (history.group.is_none() && !history.dirty) {
+        return;
+    }
+    history.record(&map.0);
+}
+
+pub struct HistoryPanel;
+
+/// Lists the latest edits down the right of the window, with the one last done highlighted and
+/// those undone greyed out.
+pub fn show_history_panel(
+    mut commands: Commands,
+    history: Res<History>,
+    existing: Query<Entity, With<HistoryPanel>>,
+    res: Res<UiResources>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !history.panel_shown {
+        return;
+    }
+
+    let done = history.undo.iter().map(|x| (x.label.as_str(), false));
+    let undone = history.redo.iter().rev().map(|x| (x.label.as_str(), true));
+    let mut rows: Vec<(&str, bool)> = std::iter::once(("(opened)", false))
+        .chain(done)
+        .chain(undone)
+        .collect();
+    let current = history.undo.len();
+    // Keep the current edit in view when there are more than fit.
+    let first = current
+        .saturating_sub(PANEL_ROWS / 2)
+        .min(rows.len().saturating_sub(PANEL_ROWS));
+    rows = rows.into_iter().skip(first).take(PANEL_ROWS).collect();
+
+    let style = |color| TextStyle {
+        font: res.font.clone(),
+        font_size: 16.0,
+        color,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(0.0),
+                    bottom: Val::Percent(30.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(220.0), Val::Auto),
+                // Children are laid out bottom up, so reverse them to read top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            material: res.panel_color.clone(),
+            ..Default::default()
+        })
+        .insert(HistoryPanel)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("History", style(Color::GRAY), Default::default()),
+                ..Default::default()
+            });
+            for (i, (label, undone)) in rows.into_iter().enumerate() {
+                let color = if first + i == current {
+                    Color::YELLOW
+                } else if undone {
+                    Color::DARK_GRAY
+                } else {
+                    Color::WHITE
+                };
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(label, style(color), Default::default()),
+                    ..Default::default()
+                });
+            }
+        });
+}
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+use fujiformer_io::lint::{lint, Severity};
+
+use super::Map;
+
+pub fn lint_on_map_load(map_create: Query<&Map, Added<Map>>) {
+    for map in map_create.iter() {
+        for diagnostic in lint(&map.0).iter() {
+            match diagnostic.severity() {
+                Severity::Error => error!("{}", diagnostic),
+                Severity::Warning => warn!("{}", diagnostic),
+            }
+        }
+    }
+}
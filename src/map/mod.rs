@@ -1,32 +1,160 @@
+mod autofill;
+mod camera;
 mod filler;
+mod history;
+mod lint;
+mod open;
+mod paint;
+mod resize;
+mod save;
 mod screen;
+mod select;
+mod sync;
+mod tiles;
 mod ui;
 
-pub use self::ui::MapCamera;
-
-use std::{fs::File, io::BufReader};
+pub use self::{open::FileBrowser, ui::MapCamera};
 
 use bevy::prelude::*;
 use fujiformer_io::CelesteMap;
 
-use self::ui::MapUiPlugin;
+use crate::ui::{no_modal_open, UiEventStage};
+
+use self::{
+    history::History,
+    open::{OpenMap, RecentFiles},
+    paint::{PaintStroke, PaintTools},
+    resize::{ScreenResize, ScreenResizeStage},
+    save::{MapFile, SaveMap},
+    screen::ScreenIndex,
+    select::ScreenDrag,
+    sync::MapSync,
+    tiles::TileChunks,
+    ui::MapUiPlugin,
+};
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_plugin(MapUiPlugin)
-            .add_startup_system(load_map.system())
-            .add_system(filler::add_on_map_load.system())
-            .add_system(screen::add_on_map_load.system());
+            .init_resource::<autofill::FillerPreview>()
+            .init_resource::<History>()
+            .init_resource::<MapFile>()
+            .init_resource::<RecentFiles>()
+            .init_resource::<FileBrowser>()
+            .init_resource::<ScreenDrag>()
+            .init_resource::<ScreenResize>()
+            .init_resource::<TileChunks>()
+            .init_resource::<ScreenIndex>()
+            .init_resource::<PaintTools>()
+            .init_resource::<PaintStroke>()
+            .add_event::<OpenMap>()
+            .add_event::<SaveMap>()
+            .add_startup_system(open::open_from_args.system())
+            .add_system(
+                autofill::filler_preview
+                    .system()
+                    .with_run_criteria(no_modal_open.system())
+                    .before(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                open::open_shortcuts
+                    .system()
+                    .with_run_criteria(no_modal_open.system()),
+            )
+            .add_system(open::browser_input.system().label(UiEventStage))
+            .add_system(open::show_browser.system().after(UiEventStage))
+            .add_system(open::open_submitted.system())
+            .add_system(open::open_map.system())
+            .add_system(open::show_empty_workspace_hint.system())
+            .add_system(
+                save::save_shortcuts
+                    .system()
+                    .with_run_criteria(no_modal_open.system()),
+            )
+            .add_system(save::save_as_submitted.system())
+            .add_system(
+                paint::paint_shortcuts
+                    .system()
+                    .with_run_criteria(no_modal_open.system()),
+            )
+            .add_system(paint::pick_tileset.system())
+            .add_system(paint::show_tileset_picker.system())
+            .add_system(
+                paint::paint_tiles
+                    .system()
+                    .with_run_criteria(no_modal_open.system())
+                    .after(UiEventStage)
+                    .before(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                resize::resize_screens
+                    .system()
+                    .with_run_criteria(no_modal_open.system())
+                    .label(ScreenResizeStage)
+                    .after(UiEventStage)
+                    .before(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                select::select_screens
+                    .system()
+                    .with_run_criteria(no_modal_open.system())
+                    .after(ScreenResizeStage)
+                    .before(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                resize::update_handles
+                    .system()
+                    .after(MapSync::MapToComponents),
+            )
+            .add_system(select::highlight_selection.system())
+            .add_system(
+                history::history_shortcuts
+                    .system()
+                    .with_run_criteria(no_modal_open.system())
+                    .before(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                history::record_history
+                    .system()
+                    .after(MapSync::MapToComponents),
+            )
+            .add_system(history::show_history_panel.system())
+            .add_system(
+                tiles::update_tile_chunks
+                    .system()
+                    .after(MapSync::MapToComponents),
+            )
+            .add_system(save::save_map.system().after(MapSync::ComponentsToMap))
+            .add_system(lint::lint_on_map_load.system())
+            .add_system(
+                sync::components_to_map
+                    .system()
+                    .label(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                sync::map_to_components
+                    .system()
+                    .label(MapSync::MapToComponents)
+                    .after(MapSync::ComponentsToMap),
+            )
+            .add_system(
+                filler::update_sprites
+                    .system()
+                    .after(MapSync::MapToComponents),
+            )
+            .add_system(
+                screen::update_sprites
+                    .system()
+                    .after(MapSync::MapToComponents),
+            )
+            .add_system(
+                screen::update_index
+                    .system()
+                    .after(MapSync::MapToComponents),
+            );
     }
 }
 
 pub struct Map(CelesteMap);
-
-fn load_map(mut commands: Commands) {
-    let map_path = std::env::args().nth(1).unwrap();
-    let map_file = BufReader::new(File::open(map_path).unwrap());
-    let map = CelesteMap::read(map_file).unwrap();
-    commands.spawn().insert(Map(map));
-}
@@ -0,0 +1,403 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use fujiformer_io::{CelesteMap, Filler, Screen};
+
+use super::{
+    autofill::{FillerPreview, FillerPreviewSprite},
+    save::MapFile,
+    Map,
+};
+use crate::{
+    dialog::ErrorDialog,
+    prompt::{Prompt, PromptPurpose, PromptSubmitted, UiResources},
+};
+
+/// How many maps the recent files list remembers.
+const RECENT_FILES_LEN: usize = 10;
+
+/// Requests the map at a path replace the open one.
+pub struct OpenMap(pub PathBuf);
+
+/// Maps recently opened or saved, most recent first, kept between runs in the config directory.
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl FromWorld for RecentFiles {
+    fn from_world(_world: &mut World) -> Self {
+        let paths = recent_files_path()
+            .and_then(|x| fs::read_to_string(x).ok())
+            .map(|x| x.lines().map(PathBuf::from).collect())
+            .unwrap_or_default();
+        RecentFiles { paths }
+    }
+}
+
+impl RecentFiles {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Moves `path` to the front of the list and stores the list.
+    pub fn push(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|x| *x != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(RECENT_FILES_LEN);
+
+        let stored = recent_files_path().map_or(Ok(()), |file| {
+            if let Some(dir) = file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let lines: Vec<String> = self.paths.iter().map(|x| x.display().to_string()).collect();
+            fs::write(file, lines.join("\n"))
+        });
+        if let Err(e) = stored {
+            warn!("failed to store recent files: {}", e);
+        }
+    }
+}
+
+fn recent_files_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|x| PathBuf::from(x).join(".config")))?;
+    Some(config.join("fujiformer").join("recent_files"))
+}
+
+/// Opens the map given as the first command line argument, if any.
+pub fn open_from_args(mut open: EventWriter<OpenMap>) {
+    if let Some(path) = env::args_os().nth(1) {
+        open.send(OpenMap(PathBuf::from(path)));
+    }
+}
+
+/// Ctrl+O shows the file browser, and Ctrl+Shift+O asks for a path to type.
+pub fn open_shortcuts(
+    keys: Res<Input<KeyCode>>,
+    map_file: Res<MapFile>,
+    recent: Res<RecentFiles>,
+    mut browser: ResMut<FileBrowser>,
+    mut prompt: ResMut<Prompt>,
+) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if !ctrl || !keys.just_pressed(KeyCode::O) {
+        return;
+    }
+
+    let dir = map_file
+        .path
+        .as_ref()
+        .and_then(|x| x.parent())
+        .filter(|x| !x.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+    if shift {
+        prompt.open(
+            PromptPurpose::Open,
+            "Open",
+            format!("{}{}", dir.display(), std::path::MAIN_SEPARATOR),
+        );
+    } else {
+        browser.open(dir, recent.paths());
+    }
+}
+
+pub fn open_submitted(mut submitted: EventReader<PromptSubmitted>, mut open: EventWriter<OpenMap>) {
+    for event in submitted.iter() {
+        if event.purpose == PromptPurpose::Open && !event.text.is_empty() {
+            open.send(OpenMap(PathBuf::from(&event.text)));
+        }
+    }
+}
+
+/// Entities spawned for the open map, which go when another is opened.
+type OpenedWithMap = Or<(
+    With<Map>,
+    With<Screen>,
+    With<Filler>,
+    With<FillerPreviewSprite>,
+)>;
+
+/// Replaces everything from the open map with the requested one, or shows why it can't be read.
+pub fn open_map(
+    mut commands: Commands,
+    mut requests: EventReader<OpenMap>,
+    opened: Query<Entity, OpenedWithMap>,
+    mut preview: ResMut<FillerPreview>,
+    mut map_file: ResMut<MapFile>,
+    mut recent: ResMut<RecentFiles>,
+    mut dialog: ResMut<ErrorDialog>,
+) {
+    // Only the last request matters, as each would replace the one before.
+    let OpenMap(path) = match requests.iter().last() {
+        Some(x) => x,
+        None => return,
+    };
+    let map = match CelesteMap::open(path) {
+        Ok(x) => x,
+        Err(e) => {
+            error!("failed to open map {}: {}", path.display(), e);
+            dialog.show(format!("Failed to open {}", path.display()), &e);
+            return;
+        }
+    };
+
+    for entity in opened.iter() {
+        commands.entity(entity).despawn();
+    }
+    preview.hide();
+    commands.spawn().insert(Map(map));
+    info!("opened map {}", path.display());
+    recent.push(path);
+    map_file.path = Some(path.clone());
+}
+
+/// A list of recent files followed by the contents of a directory, picked from with the keyboard.
+#[derive(Default)]
+pub struct FileBrowser(Option<OpenBrowser>);
+
+struct OpenBrowser {
+    dir: PathBuf,
+    entries: Vec<BrowserEntry>,
+    selected: usize,
+}
+
+struct BrowserEntry {
+    label: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+impl FileBrowser {
+    pub fn open(&mut self, dir: PathBuf, recent: &[PathBuf]) {
+        let mut entries: Vec<BrowserEntry> = recent
+            .iter()
+            .map(|x| BrowserEntry {
+                label: format!("recent: {}", x.display()),
+                path: x.clone(),
+                is_dir: false,
+            })
+            .collect();
+        if let Some(parent) = dir.parent() {
+            entries.push(BrowserEntry {
+                label: "../".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            });
+        }
+
+        let mut listed: Vec<BrowserEntry> = fs::read_dir(&dir)
+            .map(|x| x.filter_map(Result::ok).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|x| {
+                let name = x.file_name().to_string_lossy().into_owned();
+                let path = x.path();
+                let is_dir = path.is_dir();
+                let is_map = path.extension().and_then(|x| x.to_str()) == Some("bin");
+                if name.starts_with('.') || !(is_dir || is_map) {
+                    return None;
+                }
+                Some(BrowserEntry {
+                    label: if is_dir { format!("{}/", name) } else { name },
+                    path,
+                    is_dir,
+                })
+            })
+            .collect();
+        listed.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.label.cmp(&b.label)));
+        entries.extend(listed);
+
+        self.0 = Some(OpenBrowser {
+            dir,
+            entries,
+            selected: 0,
+        });
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Up and Down pick an entry, Enter opens it, Backspace goes up a directory and Escape closes.
+pub fn browser_input(
+    keys: Res<Input<KeyCode>>,
+    recent: Res<RecentFiles>,
+    mut browser: ResMut<FileBrowser>,
+    mut open: EventWriter<OpenMap>,
+) {
+    let (dir, selected, len) = match browser.0.as_ref() {
+        Some(x) => (x.dir.clone(), x.selected, x.entries.len()),
+        None => return,
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        browser.0 = None;
+    } else if keys.just_pressed(KeyCode::Up) && selected > 0 {
+        if let Some(x) = browser.0.as_mut() {
+            x.selected -= 1;
+        }
+    } else if keys.just_pressed(KeyCode::Down) && selected + 1 < len {
+        if let Some(x) = browser.0.as_mut() {
+            x.selected += 1;
+        }
+    } else if keys.just_pressed(KeyCode::Back) {
+        if let Some(parent) = dir.parent() {
+            browser.open(parent.to_path_buf(), recent.paths());
+        }
+    } else if keys.just_pressed(KeyCode::Return) {
+        let entry = browser
+            .0
+            .as_ref()
+            .and_then(|x| x.entries.get(selected))
+            .map(|x| (x.path.clone(), x.is_dir));
+        match entry {
+            Some((path, true)) => browser.open(path, recent.paths()),
+            Some((path, false)) => {
+                browser.0 = None;
+                open.send(OpenMap(path));
+            }
+            None => {}
+        }
+    }
+}
+
+/// How many entries the browser shows at once, scrolling to keep the selection in view.
+const BROWSER_ROWS: usize = 24;
+
+pub struct FileBrowserUi;
+
+pub fn show_browser(
+    mut commands: Commands,
+    browser: Res<FileBrowser>,
+    existing: Query<Entity, With<FileBrowserUi>>,
+    res: Res<UiResources>,
+) {
+    if !browser.is_changed() {
+        return;
+    }
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let open = match browser.0.as_ref() {
+        Some(x) => x,
+        None => return,
+    };
+
+    let first = open
+        .selected
+        .saturating_sub(BROWSER_ROWS / 2)
+        .min(open.entries.len().saturating_sub(BROWSER_ROWS));
+    let style = |color| TextStyle {
+        font: res.font.clone(),
+        font_size: 18.0,
+        color,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(20.0),
+                    bottom: Val::Percent(10.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(60.0), Val::Percent(80.0)),
+                // Children are laid out bottom up, so reverse them to read top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(16.0)),
+                ..Default::default()
+            },
+            material: res.panel_color.clone(),
+            ..Default::default()
+        })
+        .insert(FileBrowserUi)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    format!("Open map from {}", open.dir.display()),
+                    style(Color::GRAY),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            if open.entries.is_empty() {
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "No maps here",
+                        style(Color::GRAY),
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                });
+            }
+            for (i, entry) in open
+                .entries
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(BROWSER_ROWS)
+            {
+                let color = if i == open.selected {
+                    Color::YELLOW
+                } else {
+                    Color::WHITE
+                };
+                parent.spawn_bundle(TextBundle {
+                    text: Text::with_section(entry.label.clone(), style(color), Default::default()),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+/// Text shown while no map is open, saying how to open one.
+pub struct EmptyWorkspaceHint;
+
+pub fn show_empty_workspace_hint(
+    mut commands: Commands,
+    maps: Query<(), With<Map>>,
+    hints: Query<Entity, With<EmptyWorkspaceHint>>,
+    res: Res<UiResources>,
+) {
+    let empty = maps.iter().next().is_none();
+    let shown = hints.iter().next().is_some();
+    if empty && !shown {
+        commands
+            .spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Percent(30.0),
+                        bottom: Val::Percent(50.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    "No map open. Press Ctrl+O to browse for one, or Ctrl+Shift+O to type a path.",
+                    TextStyle {
+                        font: res.font.clone(),
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            })
+            .insert(EmptyWorkspaceHint);
+    } else if !empty && shown {
+        for entity in hints.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
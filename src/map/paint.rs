@@ -0,0 +1,460 @@
+//! Painting a screen's foreground or background tiles.
+//!
+//! While a paint tool is chosen, the left mouse button paints on the screen under the cursor
+//! instead of selecting screens. Each stroke, from press to release, is undone as one edit.
+
+use bevy::prelude::*;
+use fujiformer_geom::{rasterise_line, rasterise_rect, Point, Rounding, ScreenTiles};
+use fujiformer_io::{Screen, Tiles, AIR};
+
+use super::{
+    camera::CameraDrag,
+    history::History,
+    screen::ScreenIndex,
+    tiles::{tile_color, Layer},
+    ui::MouseMapPosition,
+};
+use crate::prompt::UiResources;
+
+/// The largest brush, in tiles across.
+const MAX_BRUSH_SIZE: u32 = 16;
+
+/// The vanilla foreground tilesets, by the character stored in the map.
+const FG_TILESETS: [(char, &str); 24] = [
+    ('1', "Dirt"),
+    ('3', "Snow"),
+    ('4', "Girder"),
+    ('5', "Tower"),
+    ('6', "Stone"),
+    ('7', "Cement"),
+    ('8', "Rock"),
+    ('9', "Wood"),
+    ('a', "Wood Stone Edges"),
+    ('b', "Cliffside"),
+    ('c', "Pool Edges"),
+    ('d', "Temple A"),
+    ('e', "Temple B"),
+    ('f', "Cliffside Alt"),
+    ('g', "Reflection"),
+    ('G', "Reflection Alt"),
+    ('h', "Grass"),
+    ('i', "Summit"),
+    ('j', "Summit No Snow"),
+    ('k', "Core"),
+    ('l', "Dead Grass"),
+    ('m', "Lost Levels"),
+    ('n', "Scifi"),
+    ('o', "Template"),
+];
+
+/// The vanilla background tilesets, by the character stored in the map.
+const BG_TILESETS: [(char, &str); 11] = [
+    ('1', "Dirt"),
+    ('2', "Brick"),
+    ('3', "Brick Ruined"),
+    ('4', "Wood"),
+    ('5', "Resort Stone"),
+    ('6', "Cliffside"),
+    ('7', "Pool"),
+    ('8', "Temple A"),
+    ('9', "Temple B"),
+    ('a', "Reflection"),
+    ('b', "Core"),
+];
+
+fn tilesets(layer: Layer) -> &'static [(char, &'static str)] {
+    match layer {
+        Layer::Bg => &BG_TILESETS,
+        Layer::Solids => &FG_TILESETS,
+    }
+}
+
+type TilePoint = Point<i32, ScreenTiles>;
+// Named apart from bevy's UI `Rect`.
+type TileRect = fujiformer_geom::Rect<i32, u32, ScreenTiles>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaintTool {
+    Brush,
+    Eraser,
+    Rect,
+    RectOutline,
+    Line,
+    Fill,
+}
+
+impl PaintTool {
+    fn name(self) -> &'static str {
+        match self {
+            PaintTool::Brush => "brush",
+            PaintTool::Eraser => "eraser",
+            PaintTool::Rect => "rectangle",
+            PaintTool::RectOutline => "rectangle outline",
+            PaintTool::Line => "line",
+            PaintTool::Fill => "flood fill",
+        }
+    }
+}
+
+/// The chosen paint tool and what it paints with.
+pub struct PaintTools {
+    /// `None` while the mouse selects and moves screens instead.
+    pub tool: Option<PaintTool>,
+    pub layer: Layer,
+    pub tile: char,
+    /// Width and height of the brush and eraser, in tiles.
+    pub brush_size: u32,
+}
+
+impl Default for PaintTools {
+    fn default() -> Self {
+        PaintTools {
+            tool: None,
+            layer: Layer::Solids,
+            tile: FG_TILESETS[0].0,
+            brush_size: 1,
+        }
+    }
+}
+
+impl PaintTools {
+    /// Whether a paint tool is chosen, so the mouse shouldn't also select or resize screens.
+    pub fn is_painting(&self) -> bool {
+        self.tool.is_some()
+    }
+}
+
+/// The stroke being painted, if any.
+#[derive(Default)]
+pub struct PaintStroke(Option<Stroke>);
+
+struct Stroke {
+    screen: Entity,
+    tool: PaintTool,
+    layer: Layer,
+    start: TilePoint,
+    last: TilePoint,
+    /// The layer as it was when the stroke began, which shapes are redrawn over as they change.
+    original: Tiles,
+}
+
+/// The cells covered by a square brush of width `size` centred on `point`.
+fn brush_cells(point: TilePoint, size: u32) -> Vec<TilePoint> {
+    let before = (size as i32 - 1) / 2;
+    let after = size as i32 - 1 - before;
+    rasterise_rect(&corner_rect(
+        point - Point::new(before, before),
+        point + Point::new(after, after),
+    ))
+}
+
+/// The rect with `a` and `b` as opposite corners, both included.
+fn corner_rect(a: TilePoint, b: TilePoint) -> TileRect {
+    TileRect::from_edges(
+        a.x().min(b.x()),
+        a.y().min(b.y()),
+        a.x().max(b.x()) + 1,
+        a.y().max(b.y()) + 1,
+    )
+}
+
+/// Sets `cells` of the screen's layer to `tile`, only borrowing the screen mutably if that
+/// changes anything.
+fn paint_cells(
+    screen: &mut Mut<Screen>,
+    layer: Layer,
+    cells: impl IntoIterator<Item = TilePoint>,
+    tile: char,
+) {
+    let changed: Vec<TilePoint> = cells
+        .into_iter()
+        .filter(|x| matches!(layer.tiles(screen).grid().get(*x), Some(&old) if old != tile))
+        .collect();
+    if !changed.is_empty() {
+        layer.tiles_mut(screen).grid_mut().set_all(changed, tile);
+    }
+}
+
+/// B, E, R, L and G choose the brush, eraser, rectangle, line and flood fill, with R again
+/// switching between a filled and outlined rectangle, and V goes back to selecting screens. Tab
+/// switches layers, `[` and `]` change the brush size and `,` and `.` the tileset.
+pub fn paint_shortcuts(keys: Res<Input<KeyCode>>, mut tools: ResMut<PaintTools>) {
+    if keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl) {
+        return;
+    }
+    let current = tools.tool;
+    let tool = if keys.just_pressed(KeyCode::B) {
+        Some(Some(PaintTool::Brush))
+    } else if keys.just_pressed(KeyCode::E) {
+        Some(Some(PaintTool::Eraser))
+    } else if keys.just_pressed(KeyCode::R) {
+        if current == Some(PaintTool::Rect) {
+            Some(Some(PaintTool::RectOutline))
+        } else {
+            Some(Some(PaintTool::Rect))
+        }
+    } else if keys.just_pressed(KeyCode::L) {
+        Some(Some(PaintTool::Line))
+    } else if keys.just_pressed(KeyCode::G) {
+        Some(Some(PaintTool::Fill))
+    } else if keys.just_pressed(KeyCode::V) {
+        Some(None)
+    } else {
+        None
+    };
+    if let Some(tool) = tool {
+        if tool != current {
+            tools.tool = tool;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Tab) {
+        tools.layer = match tools.layer {
+            Layer::Bg => Layer::Solids,
+            Layer::Solids => Layer::Bg,
+        };
+        // Keep the tileset if the new layer has it.
+        let layer = tools.layer;
+        if !tilesets(layer).iter().any(|&(tile, _)| tile == tools.tile) {
+            tools.tile = tilesets(layer)[0].0;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::LBracket) && tools.brush_size > 1 {
+        tools.brush_size -= 1;
+    }
+    if keys.just_pressed(KeyCode::RBracket) && tools.brush_size < MAX_BRUSH_SIZE {
+        tools.brush_size += 1;
+    }
+
+    let step: isize = if keys.just_pressed(KeyCode::Comma) {
+        -1
+    } else if keys.just_pressed(KeyCode::Period) {
+        1
+    } else {
+        return;
+    };
+    let sets = tilesets(tools.layer);
+    let index = sets
+        .iter()
+        .position(|&(tile, _)| tile == tools.tile)
+        .unwrap_or(0) as isize;
+    tools.tile = sets[(index + step).rem_euclid(sets.len() as isize) as usize].0;
+}
+
+/// With a paint tool chosen, dragging the left mouse button paints on the screen it started on.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_tiles(
+    buttons: Res<Input<MouseButton>>,
+    mouse: Res<MouseMapPosition>,
+    camera_drag: Res<CameraDrag>,
+    tools: Res<PaintTools>,
+    mut stroke: ResMut<PaintStroke>,
+    mut history: ResMut<History>,
+    index: Res<ScreenIndex>,
+    mut screens: Query<(Entity, &mut Screen)>,
+    ui: Query<&Interaction>,
+) {
+    if stroke.0.is_some() && !buttons.pressed(MouseButton::Left) {
+        history.end();
+        stroke.0 = None;
+        return;
+    }
+    let (tool, position) = match (tools.tool, mouse.0) {
+        (Some(tool), Some(position)) => (tool, position),
+        _ => return,
+    };
+    let point = Point::new(position.x.floor() as i32, position.y.floor() as i32);
+
+    if buttons.just_pressed(MouseButton::Left) && stroke.0.is_none() {
+        // Leave clicks on the tileset picker, or starting a camera drag, alone.
+        if camera_drag.is_active() || ui.iter().any(|x| *x != Interaction::None) {
+            return;
+        }
+        let hit = index.smallest_at(point);
+        let (entity, mut screen) = match hit.and_then(|x| screens.get_mut(x).ok()) {
+            Some(x) => x,
+            None => return,
+        };
+        let start = point
+            .to_screen(screen.shape().position())
+            .to_tiles(Rounding::Floor);
+
+        history.begin(tool.name());
+        if tool == PaintTool::Fill {
+            let tiles = tools.layer.tiles(&screen);
+            if matches!(tiles.grid().get(start), Some(&old) if old != tools.tile) {
+                tools
+                    .layer
+                    .tiles_mut(&mut screen)
+                    .grid_mut()
+                    .flood_fill(start, tools.tile);
+            }
+            history.end();
+            return;
+        }
+        stroke.0 = Some(Stroke {
+            screen: entity,
+            tool,
+            layer: tools.layer,
+            start,
+            last: start,
+            original: tools.layer.tiles(&screen).clone(),
+        });
+        // Fall through so the first cell is painted straight away.
+    }
+
+    let active = match stroke.0.as_mut() {
+        Some(x) => x,
+        None => return,
+    };
+    let mut screen = match screens.get_mut(active.screen) {
+        Ok((_, screen)) => screen,
+        Err(_) => {
+            stroke.0 = None;
+            history.end();
+            return;
+        }
+    };
+    let current = point
+        .to_screen(screen.shape().position())
+        .to_tiles(Rounding::Floor);
+    let layer = active.layer;
+
+    match active.tool {
+        PaintTool::Brush | PaintTool::Eraser => {
+            let tile = if active.tool == PaintTool::Eraser {
+                AIR
+            } else {
+                tools.tile
+            };
+            // Painted along the line from the last frame's cell, so fast strokes leave no gaps.
+            let size = tools.brush_size;
+            let cells = rasterise_line(active.last, current)
+                .into_iter()
+                .flat_map(|x| brush_cells(x, size));
+            paint_cells(&mut screen, layer, cells, tile);
+        }
+        PaintTool::Rect | PaintTool::RectOutline | PaintTool::Line => {
+            let mut tiles = active.original.clone();
+            let rect = corner_rect(active.start, current);
+            match active.tool {
+                PaintTool::Rect => tiles.grid_mut().fill_rect(&rect, tools.tile),
+                PaintTool::RectOutline => tiles.grid_mut().draw_rect(&rect, tools.tile),
+                _ => tiles
+                    .grid_mut()
+                    .draw_line(active.start, current, tools.tile),
+            }
+            if *layer.tiles(&screen) != tiles {
+                *layer.tiles_mut(&mut screen) = tiles;
+            }
+        }
+        PaintTool::Fill => {}
+    }
+    active.last = current;
+}
+
+pub struct TilesetPicker;
+
+/// Which tileset a row of the picker chooses.
+pub struct TilesetButton(char);
+
+/// Clicking a row of the tileset picker chooses its tileset.
+pub fn pick_tileset(
+    mut tools: ResMut<PaintTools>,
+    buttons: Query<(&Interaction, &TilesetButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Clicked && tools.tile != button.0 {
+            tools.tile = button.0;
+        }
+    }
+}
+
+/// Lists the layer's tilesets down the left of the window while painting, in the colours
+/// they're drawn in, along with the tool and brush size.
+pub fn show_tileset_picker(
+    mut commands: Commands,
+    tools: Res<PaintTools>,
+    existing: Query<Entity, With<TilesetPicker>>,
+    res: Res<UiResources>,
+) {
+    if !tools.is_changed() {
+        return;
+    }
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let tool = match tools.tool {
+        Some(x) => x,
+        None => return,
+    };
+
+    let style = |color| TextStyle {
+        font: res.font.clone(),
+        font_size: 16.0,
+        color,
+    };
+    let heading = match tool {
+        PaintTool::Brush | PaintTool::Eraser => {
+            format!(
+                "{} {} ({})",
+                tool.name(),
+                tools.brush_size,
+                tools.layer.name()
+            )
+        }
+        _ => format!("{} ({})", tool.name(), tools.layer.name()),
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0.0),
+                    bottom: Val::Percent(30.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(200.0), Val::Auto),
+                // Children are laid out bottom up, so reverse them to read top down.
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            material: res.panel_color.clone(),
+            ..Default::default()
+        })
+        // So clicks between the rows don't paint the map underneath.
+        .insert(Interaction::default())
+        .insert(TilesetPicker)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(heading, style(Color::GRAY), Default::default()),
+                ..Default::default()
+            });
+            for &(tile, name) in tilesets(tools.layer) {
+                let [r, g, b, a] = tile_color(tile, tools.layer);
+                let marker = if tile == tools.tile { ">" } else { " " };
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Percent(100.0), Val::Auto),
+                            ..Default::default()
+                        },
+                        material: res.panel_color.clone(),
+                        ..Default::default()
+                    })
+                    .insert(TilesetButton(tile))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                format!("{} {} {}", marker, tile, name),
+                                style(Color::rgba_u8(r, g, b, a)),
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
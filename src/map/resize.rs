@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use fujiformer_geom::{IntRect, Rect, TILE_SIZE};
+use fujiformer_io::Screen;
+
+use super::{
+    camera::CameraDrag,
+    history::History,
+    paint::PaintTools,
+    select::Selected,
+    ui::{MapCamera, MapUiResources, MouseMapPosition},
+};
+
+/// Width of a resize handle on screen, in window pixels.
+const HANDLE_SIZE: f32 = 8.0;
+
+/// Which edges of a screen a handle moves, as `(left, top, right, bottom)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edges(bool, bool, bool, bool);
+
+/// The corner and edge handles, clockwise from the top left.
+const HANDLES: [Edges; 8] = [
+    Edges(true, true, false, false),
+    Edges(false, true, false, false),
+    Edges(false, true, true, false),
+    Edges(false, false, true, false),
+    Edges(false, false, true, true),
+    Edges(false, false, false, true),
+    Edges(true, false, false, true),
+    Edges(true, false, false, false),
+];
+
+fn handle_position(rect: &IntRect, edges: Edges) -> Vec2 {
+    let Edges(left, top, right, bottom) = edges;
+    let pick = |low: bool, high: bool, low_edge: i32, high_edge: i32| {
+        if low {
+            low_edge as f32
+        } else if high {
+            high_edge as f32
+        } else {
+            (low_edge + high_edge) as f32 * 0.5
+        }
+    };
+    Vec2::new(
+        pick(left, right, rect.left(), rect.right()),
+        pick(top, bottom, rect.top(), rect.bottom()),
+    )
+}
+
+/// `rect` with the handle's edges moved by `offset`, kept at least a tile wide and high.
+fn resized_rect(rect: &IntRect, edges: Edges, offset: Vec2) -> IntRect {
+    let Edges(left, top, right, bottom) = edges;
+    let tile = TILE_SIZE as i32;
+    let snap = |x: f32| (x / tile as f32).round() as i32 * tile;
+    let (dx, dy) = (snap(offset.x), snap(offset.y));
+    let (mut l, mut t, mut r, mut b) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    if left {
+        l = (l + dx).min(r - tile);
+    }
+    if right {
+        r = (r + dx).max(l + tile);
+    }
+    if top {
+        t = (t + dy).min(b - tile);
+    }
+    if bottom {
+        b = (b + dy).max(t + tile);
+    }
+    Rect::from_edges(l, t, r, b)
+}
+
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub struct ScreenResizeStage;
+
+/// One of the handles shown around each selected screen.
+pub struct ResizeHandle {
+    screen: Entity,
+    handle: usize,
+}
+
+/// Resizing a screen by dragging one of its handles.
+pub struct ScreenResize {
+    /// Whether to warn when a resize leaves entities or triggers outside their screen.
+    pub warn_outside_entities: bool,
+    active: Option<ActiveResize>,
+}
+
+struct ActiveResize {
+    screen: Entity,
+    edges: Edges,
+    start: Vec2,
+    /// The screen as it was when the drag began, so shrinking then growing again restores tiles.
+    original: Screen,
+    outside: Vec<i32>,
+}
+
+impl Default for ScreenResize {
+    fn default() -> Self {
+        ScreenResize {
+            warn_outside_entities: true,
+            active: None,
+        }
+    }
+}
+
+impl ScreenResize {
+    /// Whether a handle is being dragged, so the mouse shouldn't also select or move screens.
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+}
+
+fn camera_scale(camera: &Query<&Transform, With<MapCamera>>) -> f32 {
+    camera.iter().next().map_or(1.0, |x| x.scale.x.abs())
+}
+
+/// Keeps a handle at each corner and edge of every selected screen, the same size however far
+/// the camera is zoomed.
+pub fn update_handles(
+    mut commands: Commands,
+    screens: Query<(Entity, &Screen), With<Selected>>,
+    mut handles: Query<(Entity, &ResizeHandle, &mut Sprite, &mut Transform)>,
+    camera: Query<&Transform, With<MapCamera>>,
+    res: Res<MapUiResources>,
+) {
+    let size = Vec2::splat(HANDLE_SIZE * camera_scale(&camera));
+    let mut wanted: HashMap<(Entity, usize), Vec2> = screens
+        .iter()
+        .flat_map(|(entity, screen)| {
+            let rect = screen.shape();
+            HANDLES
+                .iter()
+                .enumerate()
+                .map(move |(i, &edges)| ((entity, i), handle_position(&rect, edges)))
+        })
+        .collect();
+
+    for (entity, handle, mut sprite, mut transform) in handles.iter_mut() {
+        match wanted.remove(&(handle.screen, handle.handle)) {
+            Some(position) => {
+                let translation = position.extend(3.0);
+                if transform.translation != translation {
+                    transform.translation = translation;
+                }
+                if sprite.size != size {
+                    sprite.size = size;
+                }
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for ((screen, handle), position) in wanted {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite::new(size),
+                material: res.handle_color.clone(),
+                transform: Transform::from_translation(position.extend(3.0)),
+                ..Default::default()
+            })
+            .insert(ResizeHandle { screen, handle });
+    }
+}
+
+/// Dragging a handle moves its edges in whole tiles, cropping or padding the screen's tiles.
+#[allow(clippy::too_many_arguments)]
+pub fn resize_screens(
+    buttons: Res<Input<MouseButton>>,
+    mouse: Res<MouseMapPosition>,
+    camera_drag: Res<CameraDrag>,
+    tools: Res<PaintTools>,
+    mut resize: ResMut<ScreenResize>,
+    mut history: ResMut<History>,
+    mut screens: Query<&mut Screen>,
+    handles: Query<&ResizeHandle>,
+    camera: Query<&Transform, With<MapCamera>>,
+) {
+    // Checked before the cursor, as the button may be released outside the window.
+    if resize.is_active() && !buttons.pressed(MouseButton::Left) {
+        history.end();
+        let warn = resize.warn_outside_entities;
+        if let Some(done) = resize.active.take() {
+            if warn && !done.outside.is_empty() {
+                warn!(
+                    "screen {} resized with entities {:?} left outside it",
+                    done.original.name(),
+                    done.outside
+                );
+            }
+        }
+        return;
+    }
+    let position = match mouse.0 {
+        Some(x) => x,
+        None => return,
+    };
+
+    if buttons.just_pressed(MouseButton::Left) && !camera_drag.is_active() && !tools.is_painting() {
+        let reach = HANDLE_SIZE * camera_scale(&camera) * 0.5;
+        let hit = handles.iter().find_map(|handle| {
+            let edges = HANDLES[handle.handle];
+            let screen = screens.get_mut(handle.screen).ok()?;
+            let offset = position - handle_position(&screen.shape(), edges);
+            if offset.x.abs() <= reach && offset.y.abs() <= reach {
+                Some((handle.screen, edges, screen.clone()))
+            } else {
+                None
+            }
+        });
+        if let Some((screen, edges, original)) = hit {
+            history.begin("resize screen");
+            resize.active = Some(ActiveResize {
+                screen,
+                edges,
+                start: position,
+                original,
+                outside: Vec::new(),
+            });
+        }
+        return;
+    }
+
+    if let Some(active) = resize.active.as_mut() {
+        let rect = resized_rect(
+            &active.original.shape(),
+            active.edges,
+            position - active.start,
+        );
+        if let Ok(mut screen) = screens.get_mut(active.screen) {
+            if screen.shape() != rect {
+                let mut resized = active.original.clone();
+                active.outside = resized.resize(rect);
+                *screen = resized;
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use super::{open::RecentFiles, Map};
+use crate::prompt::{Prompt, PromptPurpose, PromptSubmitted};
+use bevy::prelude::*;
+
+/// Where the open map was read from or last saved to.
+#[derive(Default)]
+pub struct MapFile {
+    pub path: Option<PathBuf>,
+}
+
+/// Requests the open map be saved to a path.
+pub struct SaveMap(pub PathBuf);
+
+/// Ctrl+S saves to the map's file, and Ctrl+Shift+S or saving a map with no file asks for a path.
+pub fn save_shortcuts(
+    keys: Res<Input<KeyCode>>,
+    map_file: Res<MapFile>,
+    mut prompt: ResMut<Prompt>,
+    mut save: EventWriter<SaveMap>,
+) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+    if !ctrl || !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    match &map_file.path {
+        Some(path) if !shift => save.send(SaveMap(path.clone())),
+        path => {
+            let text = path
+                .as_ref()
+                .map_or_else(String::new, |x| x.display().to_string());
+            prompt.open(PromptPurpose::SaveAs, "Save as", text);
+        }
+    }
+}
+
+pub fn save_as_submitted(
+    mut submitted: EventReader<PromptSubmitted>,
+    mut save: EventWriter<SaveMap>,
+) {
+    for event in submitted.iter() {
+        if event.purpose == PromptPurpose::SaveAs && !event.text.is_empty() {
+            save.send(SaveMap(PathBuf::from(&event.text)));
+        }
+    }
+}
+
+pub fn save_map(
+    mut requests: EventReader<SaveMap>,
+    maps: Query<&Map>,
+    mut map_file: ResMut<MapFile>,
+    mut recent: ResMut<RecentFiles>,
+) {
+    for SaveMap(path) in requests.iter() {
+        let map = match maps.iter().next() {
+            Some(x) => x,
+            None => {
+                warn!("no map open to save");
+                return;
+            }
+        };
+        match map.0.save(path) {
+            Ok(()) => {
+                info!("saved map to {}", path.display());
+                recent.push(path);
+                map_file.path = Some(path.clone());
+            }
+            Err(e) => error!("failed to save map to {}: {}", path.display(), e),
+        }
+    }
+}
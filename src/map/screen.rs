@@ -1,42 +1,77 @@
 use bevy::prelude::*;
+use fujiformer_geom::{IntPoint, SpatialIndex};
 use fujiformer_io::Screen;
 
-use super::{ui::MapUiResources, Map};
+use super::{sync::MapIndex, ui::MapUiResources};
 
 #[derive(Bundle)]
 pub struct ScreenBundle {
     screen: Screen,
+    index: MapIndex,
     #[bundle]
     sprite_bundle: SpriteBundle,
 }
 
-pub fn add_on_map_load(
-    mut commands: Commands,
-    map_create: Query<&Map, Added<Map>>,
-    res: Res<MapUiResources>,
+impl ScreenBundle {
+    pub fn new(screen: Screen, index: usize, res: &MapUiResources) -> Self {
+        let rect = screen.shape();
+        ScreenBundle {
+            screen,
+            index: MapIndex(index),
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite::new(rect.sprite_size()),
+                material: res.screen_color.clone(),
+                transform: rect.centred_transform(0.0),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Moves and resizes the sprites of screens that have changed.
+pub fn update_sprites(mut screens: Query<(&Screen, &mut Sprite, &mut Transform), Changed<Screen>>) {
+    for (screen, mut sprite, mut transform) in screens.iter_mut() {
+        let rect = screen.shape();
+        sprite.size = rect.sprite_size();
+        *transform = rect.centred_transform(transform.translation.z);
+    }
+}
+
+/// The shapes of the screen entities, for finding screens under the mouse without scanning them
+/// all.
+#[derive(Default)]
+pub struct ScreenIndex(SpatialIndex<Entity, i32, u32>);
+
+impl ScreenIndex {
+    /// The smallest screen containing `point`, as it can't be clicked anywhere else.
+    pub fn smallest_at(&self, point: IntPoint) -> Option<Entity> {
+        self.0
+            .query_point(point)
+            .into_iter()
+            .filter_map(|x| Some((x, self.0.get(x)?.size())))
+            .min_by_key(|(_, size)| size.width() as u64 * size.height() as u64)
+            .map(|(x, _)| x)
+    }
+}
+
+/// Keeps the screen index in step with the screens that have been added, changed or despawned.
+pub fn update_index(
+    mut index: ResMut<ScreenIndex>,
+    screens: Query<(Entity, &Screen, ChangeTrackers<Screen>)>,
 ) {
-    if let Some(map) = map_create.iter().next() {
-        for screen in map.0.screens().iter() {
-            let rect = screen.shape();
-            let size = rect.size();
-            let width = size.width() as f32;
-            let height = size.height() as f32;
-            commands.spawn_bundle(ScreenBundle {
-                screen: screen.clone(),
-                sprite_bundle: SpriteBundle {
-                    sprite: Sprite::new(Vec2::new(width, height)),
-                    material: res.screen_color.clone(),
-                    transform: {
-                        let pos = rect.position();
-                        Transform::from_xyz(
-                            pos.x() as f32 + width * 0.5,
-                            pos.y() as f32 + height * 0.5,
-                            0.0,
-                        )
-                    },
-                    ..Default::default()
-                },
-            });
+    let gone: Vec<Entity> = index
+        .0
+        .iter()
+        .map(|(x, _)| x)
+        .filter(|x| screens.get(*x).is_err())
+        .collect();
+    for screen in gone {
+        index.0.remove(screen);
+    }
+
+    for (entity, screen, tracker) in screens.iter() {
+        if tracker.is_changed() {
+            index.0.insert(entity, screen.shape());
         }
     }
 }
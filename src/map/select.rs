@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use fujiformer_geom::{IntPoint, IntRect, Point, Rect, TILE_SIZE};
+use fujiformer_io::Screen;
+
+use super::{
+    camera::CameraDrag,
+    history::History,
+    paint::PaintTools,
+    resize::ScreenResize,
+    screen::ScreenIndex,
+    ui::{MapUiResources, MouseMapPosition},
+};
+
+/// Marks a selected screen.
+pub struct Selected;
+
+/// What dragging with the left mouse button is doing, if anything.
+#[derive(Default)]
+pub struct ScreenDrag(Option<Drag>);
+
+enum Drag {
+    /// Moving the selected screens, each from where it was when the drag began.
+    Moving {
+        start: Vec2,
+        origins: Vec<(Entity, IntPoint)>,
+    },
+    /// Selecting every screen touching the rect from `start` to the mouse.
+    Marquee { start: Vec2, additive: bool },
+}
+
+pub struct MarqueeSprite;
+
+fn map_point(position: Vec2) -> IntPoint {
+    Point::new(position.x.floor() as i32, position.y.floor() as i32)
+}
+
+fn marquee_rect(start: Vec2, end: Vec2) -> IntRect {
+    let (low, high) = (start.min(end), start.max(end));
+    Rect::from_edges(
+        low.x.floor() as i32,
+        low.y.floor() as i32,
+        high.x.ceil() as i32,
+        high.y.ceil() as i32,
+    )
+}
+
+/// The distance dragged, rounded to whole tiles so screens on the tile grid stay on it.
+fn snapped_offset(start: Vec2, end: Vec2) -> IntPoint {
+    let tiles = (end - start) / TILE_SIZE as f32;
+    Point::new(
+        tiles.x.round() as i32 * TILE_SIZE as i32,
+        tiles.y.round() as i32 * TILE_SIZE as i32,
+    )
+}
+
+/// Clicking a screen selects it and shift-clicking adds or removes it. Dragging a selected screen
+/// moves every selected screen, and dragging from empty space selects with a marquee.
+#[allow(clippy::too_many_arguments)]
+pub fn select_screens(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<MouseMapPosition>,
+    resize: Res<ScreenResize>,
+    camera_drag: Res<CameraDrag>,
+    tools: Res<PaintTools>,
+    mut drag: ResMut<ScreenDrag>,
+    mut history: ResMut<History>,
+    index: Res<ScreenIndex>,
+    mut screens: Query<(Entity, &mut Screen, Option<&Selected>)>,
+    mut marquees: Query<(Entity, &mut Sprite, &mut Transform), With<MarqueeSprite>>,
+    res: Res<MapUiResources>,
+) {
+    let position = match mouse.0 {
+        Some(x) => x,
+        None => {
+            // Released outside the window, where there's no marquee to finish but moves still end.
+            if !buttons.pressed(MouseButton::Left) && drag.0.is_some() {
+                let marquees = marquees.iter_mut().map(|(entity, _, _)| entity);
+                finish_drag(&mut commands, &mut drag, &mut history, marquees);
+            }
+            return;
+        }
+    };
+    if resize.is_active() || camera_drag.is_active() || tools.is_painting() {
+        return;
+    }
+    let shift = keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift);
+
+    if buttons.just_pressed(MouseButton::Left) {
+        let hit = index
+            .smallest_at(map_point(position))
+            .and_then(|x| screens.get_mut(x).ok())
+            .map(|(entity, _, selected)| (entity, selected.is_some()));
+
+        match hit {
+            Some((entity, true)) if shift => {
+                commands.entity(entity).remove::<Selected>();
+            }
+            Some((entity, false)) if shift => {
+                commands.entity(entity).insert(Selected);
+            }
+            Some((entity, was_selected)) => {
+                if !was_selected {
+                    for (other, _, selected) in screens.iter_mut() {
+                        if selected.is_some() {
+                            commands.entity(other).remove::<Selected>();
+                        }
+                    }
+                    commands.entity(entity).insert(Selected);
+                }
+                let origins = screens
+                    .iter_mut()
+                    .filter(|(other, _, selected)| {
+                        *other == entity || (was_selected && selected.is_some())
+                    })
+                    .map(|(other, screen, _)| (other, screen.shape().position()))
+                    .collect();
+                history.begin("move screens");
+                drag.0 = Some(Drag::Moving {
+                    start: position,
+                    origins,
+                });
+            }
+            None => {
+                if !shift {
+                    for (other, _, selected) in screens.iter_mut() {
+                        if selected.is_some() {
+                            commands.entity(other).remove::<Selected>();
+                        }
+                    }
+                }
+                commands
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite::new(Vec2::ZERO),
+                        material: res.marquee_color.clone(),
+                        ..Default::default()
+                    })
+                    .insert(MarqueeSprite);
+                drag.0 = Some(Drag::Marquee {
+                    start: position,
+                    additive: shift,
+                });
+            }
+        }
+        return;
+    }
+
+    match &drag.0 {
+        None => {}
+        Some(Drag::Moving { start, origins }) => {
+            let offset = snapped_offset(*start, position);
+            for &(entity, origin) in origins.iter() {
+                if let Ok((_, mut screen, _)) = screens.get_mut(entity) {
+                    let moved = Rect::new(origin + offset, screen.shape().size());
+                    if screen.shape() != moved {
+                        *screen.shape_mut() = moved;
+                    }
+                }
+            }
+        }
+        Some(Drag::Marquee { start, additive }) => {
+            let rect = marquee_rect(*start, position);
+            if buttons.pressed(MouseButton::Left) {
+                for (_, mut sprite, mut transform) in marquees.iter_mut() {
+                    sprite.size = rect.sprite_size();
+                    *transform = rect.centred_transform(2.0);
+                }
+            } else {
+                for (entity, screen, selected) in screens.iter_mut() {
+                    if screen.shape().intersects(&rect) {
+                        commands.entity(entity).insert(Selected);
+                    } else if selected.is_some() && !additive {
+                        commands.entity(entity).remove::<Selected>();
+                    }
+                }
+            }
+        }
+    }
+
+    if !buttons.pressed(MouseButton::Left) && drag.0.is_some() {
+        let marquees = marquees.iter_mut().map(|(entity, _, _)| entity);
+        finish_drag(&mut commands, &mut drag, &mut history, marquees);
+    }
+}
+
+/// Ends a drag once the button is released, closing the edit of a move.
+fn finish_drag(
+    commands: &mut Commands,
+    drag: &mut ScreenDrag,
+    history: &mut History,
+    marquees: impl Iterator<Item = Entity>,
+) {
+    if let Some(Drag::Moving { .. }) = drag.0 {
+        history.end();
+    }
+    for entity in marquees {
+        commands.entity(entity).despawn();
+    }
+    drag.0 = None;
+}
+
+/// Draws selected screens in the selection colour.
+pub fn highlight_selection(
+    mut screens: Query<(&mut Handle<ColorMaterial>, Option<&Selected>), With<Screen>>,
+    res: Res<MapUiResources>,
+) {
+    for (mut material, selected) in screens.iter_mut() {
+        let wanted = if selected.is_some() {
+            &res.selected_screen_color
+        } else {
+            &res.screen_color
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Keeps the map's screens and fillers and the entities showing them in step.
+//!
+//! The [`Map`] is the single source of truth. Editing a `Screen` or `Filler` component writes the
+//! change back into the map, and changing the map, such as replacing its fillers or undoing an
+//! edit, updates, spawns or despawns entities to match. Screens are matched to their entities by
+//! name and fillers by position in the map. Neither direction writes unless something differs,
+//! so every change settles within a frame.
+//!
+//! Adding and removing screens or fillers should go through the map, as despawning an entity
+//! doesn't remove what it shows from the map.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use fujiformer_io::{Filler, Screen};
+
+use super::{filler::FillerBundle, screen::ScreenBundle, ui::MapUiResources, Map};
+
+/// Where a screen or filler entity's component is in the map's list of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapIndex(pub usize);
+
+/// Systems editing `Screen` or `Filler` components, or the map, should run before
+/// [`MapSync::ComponentsToMap`].
+#[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
+pub enum MapSync {
+    ComponentsToMap,
+    MapToComponents,
+}
+
+/// Writes changed components back into the map.
+pub fn components_to_map(
+    mut maps: Query<&mut Map>,
+    screens: Query<(&Screen, &MapIndex), Changed<Screen>>,
+    fillers: Query<(&Filler, &MapIndex), Changed<Filler>>,
+) {
+    let mut map = match maps.iter_mut().next() {
+        Some(x) => x,
+        None => return,
+    };
+    for (screen, &MapIndex(i)) in screens.iter() {
+        if map.0.screens().get(i).map(|x| x != screen) == Some(true) {
+            map.0.screens_mut()[i] = screen.clone();
+        }
+    }
+    for (filler, &MapIndex(i)) in fillers.iter() {
+        if map.0.fillers().get(i).map(|x| x != filler) == Some(true) {
+            map.0.fillers_mut()[i] = filler.clone();
+        }
+    }
+}
+
+/// Updates, spawns and despawns entities to match a changed map.
+pub fn map_to_components(
+    mut commands: Commands,
+    maps: Query<&Map, Changed<Map>>,
+    mut screens: Query<(Entity, &mut Screen, &mut MapIndex), Without<Filler>>,
+    mut fillers: Query<(Entity, &mut Filler, &mut MapIndex), Without<Screen>>,
+    res: Res<MapUiResources>,
+) {
+    let map = match maps.iter().next() {
+        Some(x) => x,
+        None => return,
+    };
+
+    // A list per name, so screens sharing a name each keep an entity. Reading through `iter_mut`
+    // doesn't mark anything changed.
+    let mut by_name: HashMap<String, Vec<Entity>> = HashMap::new();
+    for (entity, screen, _) in screens.iter_mut() {
+        by_name
+            .entry(screen.name().to_string())
+            .or_default()
+            .push(entity);
+    }
+    for (i, screen) in map.0.screens().iter().enumerate() {
+        let existing = by_name.get_mut(screen.name()).and_then(|x| x.pop());
+        match existing.and_then(|x| screens.get_mut(x).ok()) {
+            Some((_, mut component, mut index)) => {
+                if *component != *screen {
+                    *component = screen.clone();
+                }
+                if *index != MapIndex(i) {
+                    *index = MapIndex(i);
+                }
+            }
+            None => {
+                commands.spawn_bundle(ScreenBundle::new(screen.clone(), i, &res));
+            }
+        }
+    }
+    for entity in by_name.drain().flat_map(|(_, x)| x) {
+        commands.entity(entity).despawn();
+    }
+
+    let mut by_index: HashMap<usize, Entity> = HashMap::new();
+    for (entity, _, index) in fillers.iter_mut() {
+        if let Some(duplicate) = by_index.insert(index.0, entity) {
+            commands.entity(duplicate).despawn();
+        }
+    }
+    for (i, filler) in map.0.fillers().iter().enumerate() {
+        match by_index.remove(&i).and_then(|x| fillers.get_mut(x).ok()) {
+            Some((_, mut component, _)) => {
+                if *component != *filler {
+                    *component = filler.clone();
+                }
+            }
+            None => {
+                commands.spawn_bundle(FillerBundle::new(filler.clone(), i, &res));
+            }
+        }
+    }
+    for entity in by_index.drain().map(|(_, x)| x) {
+        commands.entity(entity).despawn();
+    }
+}
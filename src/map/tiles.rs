@@ -0,0 +1,243 @@
+//! Drawing each screen's foreground and background tiles.
+//!
+//! Tiles are drawn in square chunks, each a single sprite whose texture has one pixel per tile,
+//! so a large map needs a few entities per screen rather than one per tile. Only chunks whose
+//! tiles change are redrawn, and chunks of nothing but air aren't spawned at all. For now each
+//! tileset is drawn as a flat colour.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::texture::{Extent3d, FilterMode, TextureDimension, TextureFormat},
+};
+use fujiformer_geom::{IntPoint, Point, Rect, Rounding, ScreenTiles};
+use fujiformer_io::{Screen, Tiles, AIR};
+
+/// Width and height of a chunk, in tiles.
+const CHUNK_TILES: u32 = 32;
+
+/// One of a screen's two layers of tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Bg,
+    Solids,
+}
+
+impl Layer {
+    pub fn tiles(self, screen: &Screen) -> &Tiles {
+        match self {
+            Layer::Bg => screen.bg(),
+            Layer::Solids => screen.solids(),
+        }
+    }
+
+    pub fn tiles_mut(self, screen: &mut Screen) -> &mut Tiles {
+        match self {
+            Layer::Bg => screen.bg_mut(),
+            Layer::Solids => screen.solids_mut(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Bg => "bg",
+            Layer::Solids => "fg",
+        }
+    }
+
+    /// Depth in front of the screen's own sprite, with the foreground in front.
+    fn z(self) -> f32 {
+        match self {
+            Layer::Bg => 0.1,
+            Layer::Solids => 0.2,
+        }
+    }
+}
+
+/// A colour for each tileset, spread around the colour wheel so neighbouring tilesets differ,
+/// with the background darker than the foreground.
+pub fn tile_color(tile: char, layer: Layer) -> [u8; 4] {
+    // Multiples of the golden angle never land close to each other.
+    let hue = (tile as u32 as f32 * 137.508) % 360.0;
+    let lightness = match layer {
+        Layer::Bg => 0.3,
+        Layer::Solids => 0.55,
+    };
+    let [r, g, b, a] = Color::hsl(hue, 0.5, lightness).as_rgba_f32();
+    let byte = |x: f32| (x * 255.0).round() as u8;
+    [byte(r), byte(g), byte(b), byte(a)]
+}
+
+type ChunkRect = Rect<i32, u32, ScreenTiles>;
+
+/// The cells of `tiles` covered by the chunk at `index`.
+fn chunk_rect(tiles: &Tiles, index: IntPoint) -> ChunkRect {
+    let size = CHUNK_TILES as i32;
+    let chunk = Rect::from_edges(
+        index.x() * size,
+        index.y() * size,
+        (index.x() + 1) * size,
+        (index.y() + 1) * size,
+    );
+    chunk
+        .intersection(&tiles.grid().bounds())
+        .unwrap_or_else(|| Rect::from_edges(0, 0, 0, 0))
+}
+
+/// One pixel per tile, or `None` if every tile in the chunk is air.
+fn chunk_texture(tiles: &Tiles, rect: &ChunkRect, layer: Layer) -> Option<Texture> {
+    let mut data =
+        Vec::with_capacity(rect.size().width() as usize * rect.size().height() as usize * 4);
+    let mut empty = true;
+    // The map camera flips `y`, so rows are stored bottom up to show the right way up.
+    for y in (rect.top()..rect.bottom()).rev() {
+        for x in rect.left()..rect.right() {
+            let tile = tiles.grid().get(Point::new(x, y)).copied().unwrap_or(AIR);
+            if tile == AIR {
+                data.extend_from_slice(&[0; 4]);
+            } else {
+                empty = false;
+                data.extend_from_slice(&tile_color(tile, layer));
+            }
+        }
+    }
+    if empty {
+        return None;
+    }
+
+    let mut texture = Texture::new(
+        Extent3d::new(rect.size().width(), rect.size().height(), 1),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    texture.sampler.mag_filter = FilterMode::Nearest;
+    texture.sampler.min_filter = FilterMode::Nearest;
+    Some(texture)
+}
+
+/// Whether any cell in `rect` differs between `a` and `b`, which must be the same size.
+fn chunk_changed(a: &Tiles, b: &Tiles, rect: &ChunkRect) -> bool {
+    a.grid()
+        .view(rect)
+        .iter()
+        .zip(b.grid().view(rect).iter())
+        .any(|((_, a), (_, b))| a != b)
+}
+
+/// Marks a sprite showing a chunk of a screen's tiles.
+pub struct TileChunk;
+
+/// The tiles each screen's chunks were last drawn from, to tell which need redrawing.
+#[derive(Default)]
+pub struct TileChunks(HashMap<Entity, DrawnScreen>);
+
+struct DrawnScreen {
+    position: IntPoint,
+    bg: Tiles,
+    solids: Tiles,
+    chunks: HashMap<(Layer, IntPoint), Entity>,
+}
+
+impl DrawnScreen {
+    fn tiles(&self, layer: Layer) -> &Tiles {
+        match layer {
+            Layer::Bg => &self.bg,
+            Layer::Solids => &self.solids,
+        }
+    }
+}
+
+/// Redraws the chunks of screens whose tiles changed, moves those of screens that moved, and
+/// despawns those of screens that are gone.
+pub fn update_tile_chunks(
+    mut commands: Commands,
+    mut drawn: ResMut<TileChunks>,
+    screens: Query<(Entity, &Screen, ChangeTrackers<Screen>)>,
+    mut chunk_transforms: Query<&mut Transform, With<TileChunk>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let gone: Vec<Entity> = drawn
+        .0
+        .keys()
+        .copied()
+        .filter(|x| screens.get(*x).is_err())
+        .collect();
+    for screen in gone {
+        if let Some(old) = drawn.0.remove(&screen) {
+            for (_, chunk) in old.chunks {
+                commands.entity(chunk).despawn();
+            }
+        }
+    }
+
+    for (entity, screen, tracker) in screens.iter() {
+        if !tracker.is_changed() {
+            continue;
+        }
+        let position = screen.shape().position();
+        let previous = drawn.0.remove(&entity).unwrap_or_else(|| DrawnScreen {
+            position,
+            bg: Tiles::new(0, 0),
+            solids: Tiles::new(0, 0),
+            chunks: HashMap::new(),
+        });
+        let mut chunks = HashMap::new();
+
+        for &layer in [Layer::Bg, Layer::Solids].iter() {
+            let (tiles, old) = (layer.tiles(screen), previous.tiles(layer));
+            let resized = tiles.grid().size() != old.grid().size();
+            let chunk_count = |x: u32| Rounding::Ceil.divide(x as i32, CHUNK_TILES as i32);
+            let (columns, rows) = (chunk_count(tiles.width()), chunk_count(tiles.height()));
+
+            for index in (0..rows).flat_map(|y| (0..columns).map(move |x| Point::new(x, y))) {
+                let rect = chunk_rect(tiles, index);
+                let world = rect.to_pixels().to_world(position);
+                let existing = previous.chunks.get(&(layer, index)).copied();
+                if let Some(chunk) = existing {
+                    if !resized && !chunk_changed(tiles, old, &rect) {
+                        if position != previous.position {
+                            if let Ok(mut transform) = chunk_transforms.get_mut(chunk) {
+                                *transform = world.centred_transform(layer.z());
+                            }
+                        }
+                        chunks.insert((layer, index), chunk);
+                        continue;
+                    }
+                }
+
+                if let Some(texture) = chunk_texture(tiles, &rect, layer) {
+                    let material = materials.add(ColorMaterial::texture(textures.add(texture)));
+                    let chunk = commands
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite::new(world.sprite_size()),
+                            material,
+                            transform: world.centred_transform(layer.z()),
+                            ..Default::default()
+                        })
+                        .insert(TileChunk)
+                        .id();
+                    chunks.insert((layer, index), chunk);
+                }
+            }
+        }
+
+        // Chunks that were redrawn, or are past the edge of a screen that shrank.
+        for (key, chunk) in previous.chunks.iter() {
+            if chunks.get(key) != Some(chunk) {
+                commands.entity(*chunk).despawn();
+            }
+        }
+        drawn.0.insert(
+            entity,
+            DrawnScreen {
+                position,
+                bg: screen.bg().clone(),
+                solids: screen.solids().clone(),
+                chunks,
+            },
+        );
+    }
+}
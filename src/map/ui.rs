@@ -1,6 +1,7 @@
-use bevy::{input::mouse::MouseMotion, prelude::*};
+use bevy::prelude::*;
 
-use crate::ui::{ScrollInteraction, UiEventStage};
+use super::camera::{self, set_scale, CameraDrag, CameraTarget, CAMERA_Z};
+use crate::ui::{no_modal_open, ScrollInteraction, UiEventStage};
 
 pub struct MapUiPlugin;
 
@@ -8,7 +9,20 @@ impl Plugin for MapUiPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<MapUiResources>()
             .add_startup_system(ui_init.system())
+            .init_resource::<CameraDrag>()
             .add_system(apply_scroll.system().after(UiEventStage))
+            .add_system(camera::drag_camera.system().label(UiEventStage))
+            .add_system(
+                camera::key_pan_camera
+                    .system()
+                    .with_run_criteria(no_modal_open.system()),
+            )
+            .add_system(
+                camera::fit_camera
+                    .system()
+                    .with_run_criteria(no_modal_open.system()),
+            )
+            .add_system(camera::animate_camera.system())
             .insert_resource(MouseMapPosition(None))
             .add_system(update_mouse_map_position.system().label(UiEventStage));
     }
@@ -16,7 +30,11 @@ impl Plugin for MapUiPlugin {
 
 pub struct MapUiResources {
     pub filler_color: Handle<ColorMaterial>,
+    pub filler_preview_color: Handle<ColorMaterial>,
+    pub handle_color: Handle<ColorMaterial>,
+    pub marquee_color: Handle<ColorMaterial>,
     pub screen_color: Handle<ColorMaterial>,
+    pub selected_screen_color: Handle<ColorMaterial>,
 }
 
 impl FromWorld for MapUiResources {
@@ -24,13 +42,19 @@ impl FromWorld for MapUiResources {
         let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
         MapUiResources {
             filler_color: materials.add(Color::rgb(1.0, 1.0, 0.0).into()),
+            filler_preview_color: materials.add(Color::rgba(0.0, 1.0, 1.0, 0.5).into()),
+            handle_color: materials.add(Color::rgb(1.0, 1.0, 1.0).into()),
+            marquee_color: materials.add(Color::rgba(0.4, 0.6, 1.0, 0.3).into()),
             screen_color: materials.add(Color::rgb(0.2, 0.2, 0.2).into()),
+            selected_screen_color: materials.add(Color::rgb(0.3, 0.35, 0.5).into()),
         }
     }
 }
 
 pub fn ui_init(mut commands: Commands) {
-    commands.spawn_bundle(MapCameraBundle::default());
+    commands
+        .spawn_bundle(MapCameraBundle::default())
+        .insert(CameraTarget::default());
 }
 
 pub struct MapCamera;
@@ -55,38 +79,47 @@ impl Default for MapCameraBundle {
     }
 }
 
+/// How much one line of scrolling zooms by.
+const ZOOM_PER_LINE: f32 = 0.8;
+
 fn apply_scroll(
-    mut camera: Query<(&mut Transform, &mut ScrollInteraction), Changed<ScrollInteraction>>,
+    mut camera: Query<
+        (&mut Transform, &ScrollInteraction, &mut CameraTarget),
+        Changed<ScrollInteraction>,
+    >,
     mouse_map_position: Res<MouseMapPosition>,
 ) {
-    for (mut transform, mut scroll) in camera.iter_mut() {
-        if let Some(ref mut scroll) = scroll.0 {
-            // It's not possible for this to fail as `ScrollInteraction` has just changed.
-            let map_position = mouse_map_position.0.unwrap();
+    for (mut transform, scroll, mut target) in camera.iter_mut() {
+        if scroll.pan != Vec2::ZERO {
+            // Content follows the fingers, so the camera moves the other way.
+            transform.translation.x -= scroll.pan.x * transform.scale.x;
+            transform.translation.y += scroll.pan.y * transform.scale.y;
+        }
+        if scroll.zoom != 0.0 {
+            // Zoom around the cursor, or the middle of the window if it's outside.
+            let center = transform.translation.truncate();
+            let map_position = mouse_map_position.0.unwrap_or(center);
 
-            let scale_factor = if scroll.y > 0.0 { 0.8 } else { 1.0 / 0.8 };
+            // Scaled by how far was scrolled, so small trackpad scrolls zoom a little.
             let old_scale = transform.scale.x;
-            let new_scale = old_scale * scale_factor;
-            transform.scale.x = new_scale;
-            transform.scale.y = -new_scale;
-            let ratio = new_scale / old_scale;
-            transform.translation = (map_position * (1.0 - ratio)
-                + transform.translation.truncate() * ratio)
-                .extend(999.9);
+            set_scale(&mut transform, old_scale * ZOOM_PER_LINE.powf(scroll.zoom));
+            let ratio = transform.scale.x / old_scale;
+            transform.translation =
+                (map_position * (1.0 - ratio) + center * ratio).extend(CAMERA_Z);
         }
+        target.cancel();
     }
 }
 
-pub struct MouseMapPosition(Option<Vec2>);
+pub struct MouseMapPosition(pub Option<Vec2>);
 
 fn update_mouse_map_position(
     windows: Res<Windows>,
-    mut motion_event: EventReader<MouseMotion>,
     mut mouse_map_position: ResMut<MouseMapPosition>,
     camera: Query<&Transform, With<MapCamera>>,
 ) {
-    if motion_event.iter().next().is_some() {
-        let window = windows.get_primary().unwrap();
+    // Updated every frame, as the camera can move under a still cursor.
+    if let Some(window) = windows.get_primary() {
         mouse_map_position.0 = window
             .cursor_position()
             .map(|pos| {
@@ -0,0 +1,165 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+
+use crate::ui::UiEventStage;
+
+pub struct PromptPlugin;
+
+impl Plugin for PromptPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<UiResources>()
+            .init_resource::<Prompt>()
+            .add_event::<PromptSubmitted>()
+            .add_startup_system(spawn_ui_camera.system())
+            .add_system(prompt_input.system().label(UiEventStage))
+            .add_system(show_prompt.system().after(UiEventStage));
+    }
+}
+
+pub struct UiResources {
+    pub font: Handle<Font>,
+    pub panel_color: Handle<ColorMaterial>,
+}
+
+impl FromWorld for UiResources {
+    fn from_world(world: &mut World) -> Self {
+        let font = world
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load("fonts/DejaVuSans.ttf");
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        UiResources {
+            font,
+            panel_color: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.9).into()),
+        }
+    }
+}
+
+fn spawn_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+/// What a prompt's text is for, so the right system handles it once submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptPurpose {
+    Open,
+    SaveAs,
+}
+
+/// A single line text field, shown along the bottom of the window while open.
+#[derive(Default)]
+pub struct Prompt(Option<OpenPrompt>);
+
+struct OpenPrompt {
+    purpose: PromptPurpose,
+    title: String,
+    text: String,
+}
+
+impl Prompt {
+    pub fn open(&mut self, purpose: PromptPurpose, title: impl Into<String>, text: String) {
+        self.0 = Some(OpenPrompt {
+            purpose,
+            title: title.into(),
+            text,
+        });
+    }
+
+    /// Whether a prompt is taking keyboard input, so other shortcuts should be ignored.
+    pub fn is_open(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+pub struct PromptSubmitted {
+    pub purpose: PromptPurpose,
+    pub text: String,
+}
+
+fn prompt_input(
+    mut chars: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut prompt: ResMut<Prompt>,
+    mut submitted: EventWriter<PromptSubmitted>,
+) {
+    // Always read characters, so ones typed before the prompt opened aren't picked up later.
+    let typed: String = chars
+        .iter()
+        .map(|x| x.char)
+        .filter(|x| !x.is_control())
+        .collect();
+    // Only borrow mutably on input, so the prompt is only redrawn when it changes.
+    if !prompt.is_open() {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        prompt.0 = None;
+    } else if keys.just_pressed(KeyCode::Return) {
+        if let Some(open) = prompt.0.take() {
+            submitted.send(PromptSubmitted {
+                purpose: open.purpose,
+                text: open.text,
+            });
+        }
+    } else if keys.just_pressed(KeyCode::Back) {
+        if let Some(open) = prompt.0.as_mut() {
+            open.text.pop();
+        }
+    } else if !typed.is_empty() {
+        if let Some(open) = prompt.0.as_mut() {
+            open.text.push_str(&typed);
+        }
+    }
+}
+
+struct PromptUi;
+
+fn show_prompt(
+    mut commands: Commands,
+    prompt: Res<Prompt>,
+    existing: Query<Entity, With<PromptUi>>,
+    res: Res<UiResources>,
+) {
+    if !prompt.is_changed() {
+        return;
+    }
+    for entity in existing.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let open = match prompt.0.as_ref() {
+        Some(x) => x,
+        None => return,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                padding: Rect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            material: res.panel_color.clone(),
+            ..Default::default()
+        })
+        .insert(PromptUi)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    format!("{}: {}_", open.title, open.text),
+                    TextStyle {
+                        font: res.font.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
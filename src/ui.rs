@@ -1,6 +1,14 @@
-use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy::{
+    ecs::schedule::ShouldRun,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
 
-use crate::map::MapCamera;
+use crate::{
+    dialog::ErrorDialog,
+    map::{FileBrowser, MapCamera},
+    prompt::Prompt,
+};
 
 pub struct FfUiPlugin;
 
@@ -19,16 +27,51 @@ impl Plugin for FfUiPlugin {
 #[derive(SystemLabel, Eq, PartialEq, Hash, Clone, Debug)]
 pub struct UiEventStage;
 
+/// Pixels of a high resolution scroll that count as one line of a mouse wheel.
+const PIXELS_PER_LINE: f32 = 50.0;
+
+/// Adds up every scroll event this frame. A mouse wheel scrolls in lines and zooms. Trackpads
+/// scroll in pixels and pan, unless Ctrl is held, as trackpads send pinches as Ctrl+scroll.
 fn ui_event_handler(
     mut scroll: EventReader<MouseWheel>,
+    keys: Res<Input<KeyCode>>,
     mut map: Query<&mut ScrollInteraction, With<MapCamera>>,
 ) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let mut total = ScrollInteraction::default();
     for event in scroll.iter() {
-        if let Ok(mut scroll_event) = map.single_mut() {
-            scroll_event.0 = Some(event.clone());
+        match event.unit {
+            MouseScrollUnit::Line => total.zoom += event.y,
+            MouseScrollUnit::Pixel if ctrl => total.zoom += event.y / PIXELS_PER_LINE,
+            MouseScrollUnit::Pixel => total.pan += Vec2::new(event.x, event.y),
+        }
+    }
+    if total != ScrollInteraction::default() {
+        if let Ok(mut interaction) = map.single_mut() {
+            *interaction = total;
         }
     }
 }
 
-#[derive(Default)]
-pub struct ScrollInteraction(pub Option<MouseWheel>);
+/// Run criteria for keyboard shortcuts, which shouldn't fire while a prompt, dialog or the file
+/// browser is taking keyboard input.
+pub fn no_modal_open(
+    prompt: Res<Prompt>,
+    dialog: Res<ErrorDialog>,
+    browser: Res<FileBrowser>,
+) -> ShouldRun {
+    if prompt.is_open() || dialog.is_open() || browser.is_open() {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+/// What scrolling did this frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScrollInteraction {
+    /// Lines to zoom in by, or out by if negative.
+    pub zoom: f32,
+    /// Window pixels to move the view by, with `y` up.
+    pub pan: Vec2,
+}